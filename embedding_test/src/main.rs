@@ -1,14 +1,31 @@
 use burn::{
-    backend::{Autodiff, Metal}, nn::EmbeddingConfig, tensor::{Device, Int, Tensor}
+    nn::EmbeddingConfig,
+    tensor::{Int, Tensor, backend::Backend},
 };
 
-fn main() {
-    type MyBackend = Metal<f32, i32>;
-    type MyAutodiffBackend = Autodiff<MyBackend>;
-    let device = Device::<MyBackend>::default();
-
+fn embed<B: Backend>(device: B::Device) {
     let input_values = [1, 3, 4, 5, 9, 3, 2, 12, 24, 2, 8, 7, 10, 11, 4, 5];
-    let tensor = Tensor::<MyBackend, 1, Int>::from_data(input_values, &device);
-    let embedding = EmbeddingConfig::new()
-    println!("Hello, world!");
+    let tensor = Tensor::<B, 1, Int>::from_data(input_values, &device).reshape([4, 4]);
+    let embedding = EmbeddingConfig::new(25, 3).init::<B>(&device);
+    println!("{}", embedding.forward(tensor));
+}
+
+/// The backend is selected with `TAXIFARE_BACKEND` like in `linear_regression`,
+/// NdArray is used if it is not set.
+#[allow(unreachable_patterns)]
+fn main() {
+    let backend = std::env::var("TAXIFARE_BACKEND").unwrap_or_else(|_| "ndarray".to_string());
+    match backend.to_lowercase().as_str() {
+        #[cfg(feature = "ndarray")]
+        "ndarray" => embed::<burn::backend::NdArray<f32>>(Default::default()),
+        #[cfg(feature = "candle")]
+        "candle" => embed::<burn::backend::Candle<f32, i64>>(Default::default()),
+        #[cfg(feature = "tch")]
+        "tch" | "libtorch" => embed::<burn::backend::LibTorch<f32>>(Default::default()),
+        #[cfg(feature = "wgpu")]
+        "wgpu" => embed::<burn::backend::Wgpu<f32, i32>>(Default::default()),
+        #[cfg(feature = "metal")]
+        "metal" => embed::<burn::backend::Metal<f32, i32>>(Default::default()),
+        _ => panic!("Backend {backend} is not compiled in, enable the feature of the same name."),
+    }
 }
@@ -1,22 +1,5 @@
-use itertools::izip;
 use polars::prelude::*;
-
-const EARTH_RADIUS: f64 = 6371.0;
-///
-///Calculates the haversine distance between 2 sets of GPS coordinates in df
-///
-fn haversine_distance(plat: f64, plong: f64, dlat: f64, dlong: f64) -> f64 {
-    let phi1 = plat.to_radians();
-    let phi2 = dlat.to_radians();
-
-    let delta_phi = (dlat - plat).to_radians();
-    let delta_lambda = (dlong - plong).to_radians();
-
-    let a = (delta_phi / 2.0).sin().powi(2)
-        + phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
-    let c = 2.0 * libm::atan2(a.sqrt(), (1.0 - a).sqrt());
-    EARTH_RADIUS * c // in kilometers
-}
+use taxifare_features::{LOCAL_TIMEZONE, expressions};
 
 pub fn create_input_dataset(filename: &str) -> Result<DataFrame, PolarsError> {
     let df = CsvReadOptions::default()
@@ -40,80 +23,16 @@ pub fn create_input_dataset(filename: &str) -> Result<DataFrame, PolarsError> {
                 lit("raise"),
             )
             .dt()
-            .convert_time_zone("America/New_York".into())
+            .convert_time_zone(LOCAL_TIMEZONE.name().into())
             .alias("dt_pickup_datetime")])
-        .with_columns([
-            col("dt_pickup_datetime")
-                .dt()
-                .hour()
-                //.cast(DataType::Categorical(None, CategoricalOrdering::Physical))
-                // Does not work for numeric types
-                .alias("pickup_hour"),
-            col("dt_pickup_datetime")
-                .dt()
-                .weekday()
-                .map(
-                    |s| {
-                        let modified_workday: Series = s
-                            .as_series()
-                            .unwrap()
-                            .i8()?
-                            .iter()
-                            .map(|v| v.map(|v| v - 1))
-                            .collect();
-                        Ok(Some(modified_workday.into_column()))
-                    },
-                    GetOutput::from_type(DataType::Int8),
-                )
-                .alias("pickup_weekday"),
-        ])
-        .with_columns([when(col("pickup_hour").lt(lit(12)))
-            .then(lit(0))
-            .otherwise(lit(1))
-            //.then(lit("am"))
-            //.otherwise(lit("pm"))
-            //.cast(DataType::Categorical(None, CategoricalOrdering::Lexical))
-            .alias("am_or_pm")]);
+        .with_columns(expressions::time_features(col("dt_pickup_datetime")));
 
-    let df_distance = df.clone().select([as_struct(vec![
+    let df_distance = df.clone().select([expressions::distance(
         col("pickup_latitude"),
         col("pickup_longitude"),
         col("dropoff_latitude"),
         col("dropoff_longitude"),
-    ])
-    .map(
-        |s| {
-            let ca = s.struct_()?;
-            let plat = ca.field_by_name("pickup_latitude")?;
-            let plong = ca.field_by_name("pickup_longitude")?;
-            let dlat = ca.field_by_name("dropoff_latitude")?;
-            let dlong = ca.field_by_name("dropoff_longitude")?;
-            let plat = plat.f64()?;
-            let plong = plong.f64()?;
-            let dlat = dlat.f64()?;
-            let dlong = dlong.f64()?;
-
-            let out: Float64Chunked = izip!(
-                plat.into_iter(),
-                plong.into_iter(),
-                dlat.into_iter(),
-                dlong.into_iter()
-            )
-            .map(|(opt_plat, opt_plong, opt_dlat, opt_dlong)| {
-                match (opt_plat, opt_plong, opt_dlat, opt_dlong) {
-                    (Some(plat), Some(plong), Some(dlat), Some(dlong)) => {
-                        Some(haversine_distance(plat, plong, dlat, dlong))
-                    }
-                    _ => None,
-                }
-            })
-            .collect();
-
-            Ok(Some(out.into_column()))
-        },
-        GetOutput::from_type(DataType::Float64),
-    )
-    .alias("distance")]);
+    )]);
 
     concat_lf_horizontal([df, df_timebased, df_distance], UnionArgs::default())
         .unwrap()
@@ -18,6 +18,7 @@ fn main() {
             col("pickup_hour"),
             col("pickup_weekday"),
             col("am_or_pm"),
+            col("pickup_timestamp"),
         ])
         .collect()
         .unwrap();
@@ -0,0 +1,71 @@
+use std::marker::PhantomData;
+
+use burn::data::dataset::Dataset;
+use polars::prelude::*;
+
+pub use polars;
+
+///
+/// An item which can be read from a row of a [DataFrame].
+/// The values are passed to [DataFrameItem::from_row] in the order of
+/// [DataFrameItem::COLUMNS] followed by [DataFrameItem::OPTIONAL_COLUMNS].
+///
+pub trait DataFrameItem: Sized {
+    /// Columns which have to exist in the data frame.
+    const COLUMNS: &'static [&'static str];
+    /// Columns which are passed as [AnyValue::Null] if the data frame does not contain them.
+    const OPTIONAL_COLUMNS: &'static [&'static str] = &[];
+
+    /// Returns `None` if a value is missing or has the wrong type.
+    fn from_row(row: &[AnyValue]) -> Option<Self>;
+}
+
+/// Dataset reading its items from the rows of a [DataFrame] without serialization.
+pub struct DataFrameDataset<I> {
+    columns: Vec<Option<Column>>,
+    len: usize,
+    item: PhantomData<I>,
+}
+
+impl<I: DataFrameItem> DataFrameDataset<I> {
+    pub fn new(df: &DataFrame) -> PolarsResult<Self> {
+        let mut columns = I::COLUMNS
+            .iter()
+            .map(|name| df.column(name).map(|column| Some(column.clone())))
+            .collect::<PolarsResult<Vec<_>>>()?;
+        columns.extend(
+            I::OPTIONAL_COLUMNS
+                .iter()
+                .map(|name| df.column(name).ok().cloned()),
+        );
+        Ok(Self {
+            columns,
+            len: df.height(),
+            item: PhantomData,
+        })
+    }
+}
+
+impl<I> Dataset<I> for DataFrameDataset<I>
+where
+    I: DataFrameItem + Send + Sync,
+{
+    fn get(&self, index: usize) -> Option<I> {
+        if index >= self.len {
+            return None;
+        }
+        let row = self
+            .columns
+            .iter()
+            .map(|column| match column {
+                Some(column) => column.get(index).ok(),
+                None => Some(AnyValue::Null),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        I::from_row(&row)
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+}
@@ -0,0 +1,96 @@
+//! Compares the batcher with the former per item batching.
+//! Run with `cargo bench --bench batcher`.
+
+use std::time::{Duration, Instant};
+
+use burn::{backend::NdArray, data::dataloader::batcher::Batcher, prelude::*};
+use linear_regression::{
+    batcher::{TaxifareBatch, TaxifareBatcher},
+    dataset::mapped_dataset::TaxifareDatasetMappedItem,
+};
+
+type B = NdArray;
+
+const BATCH_SIZE: usize = 256;
+const ITERATIONS: u32 = 200;
+
+/// Creates one tensor per item and feature and concatenates them, as batching used to.
+fn batch_per_item(
+    items: &[TaxifareDatasetMappedItem],
+    device: &<B as Backend>::Device,
+) -> TaxifareBatch<B> {
+    let cont_features = items
+        .iter()
+        .map(|item| Tensor::<B, 1>::from_floats(item.continuous_features, device).reshape([1, 6]))
+        .collect();
+    let cat_feature = |feature: fn(&TaxifareDatasetMappedItem) -> u8| {
+        let tensors = items
+            .iter()
+            .map(|item| {
+                Tensor::<B, 1, Int>::from_ints([feature(item) as i64], device).reshape([1, 1])
+            })
+            .collect();
+        Tensor::cat(tensors, 0)
+    };
+    let cont_mask = items
+        .iter()
+        .map(|item| {
+            let mask = item
+                .continuous_mask
+                .map(|present| if present { 1.0 } else { 0.0 });
+            Tensor::<B, 1>::from_floats(mask, device).reshape([1, 6])
+        })
+        .collect();
+    let predictions = items
+        .iter()
+        .map(|item| Tensor::<B, 1>::from_floats([item.label], device).reshape([1, 1]))
+        .collect();
+
+    TaxifareBatch {
+        cont_features: Tensor::cat(cont_features, 0),
+        cont_mask: Tensor::cat(cont_mask, 0),
+        cat_features: vec![
+            cat_feature(|item| item.discrete_weekday),
+            cat_feature(|item| item.discrete_hour),
+            cat_feature(|item| item.discrete_am_or_pm),
+        ],
+        predictions: Tensor::cat(predictions, 0),
+        weights: None,
+    }
+}
+
+fn time_per_batch(mut batch: impl FnMut() -> TaxifareBatch<B>) -> Duration {
+    // Warm up
+    batch();
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        std::hint::black_box(batch());
+    }
+    start.elapsed() / ITERATIONS
+}
+
+fn main() {
+    let device = Default::default();
+    let items = (0..BATCH_SIZE)
+        .map(|index| TaxifareDatasetMappedItem {
+            discrete_weekday: (index % 7) as u8,
+            discrete_hour: (index % 24) as u8,
+            discrete_am_or_pm: (index % 2) as u8,
+            continuous_features: [index as f64; 6],
+            continuous_mask: [true; 6],
+            label: index as f64,
+            weight: None,
+        })
+        .collect::<Vec<_>>();
+
+    let per_item = time_per_batch(|| batch_per_item(&items, &device));
+    let vectorised = time_per_batch(|| TaxifareBatcher.batch(items.clone(), &device));
+
+    println!("Batch size {BATCH_SIZE}, mean of {ITERATIONS} batches");
+    println!("  per item tensors: {per_item:?}");
+    println!("  single tensor:    {vectorised:?}");
+    println!(
+        "  speedup:          {:.1}x",
+        per_item.as_secs_f64() / vectorised.as_secs_f64()
+    );
+}
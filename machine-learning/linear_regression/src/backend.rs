@@ -0,0 +1,146 @@
+use std::{fmt::Display, str::FromStr};
+
+use burn::{backend, tensor::backend::AutodiffBackend};
+
+/// Environment variable selecting the backend at runtime, e.g. `TAXIFARE_BACKEND=wgpu`.
+pub const BACKEND_ENV: &str = "TAXIFARE_BACKEND";
+
+///
+/// Backends the binaries can run on.
+/// Each one is compiled in by the cargo feature of the same name, only `ndarray` is a default
+/// feature so that `cargo run` works without a GPU.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BackendKind {
+    #[default]
+    NdArray,
+    /// Candle on the CPU.
+    Candle,
+    /// LibTorch on the CPU.
+    LibTorch,
+    Wgpu,
+    Metal,
+}
+
+/// Work which is generic over the backend, see [BackendKind::run].
+pub trait BackendTask {
+    type Output;
+
+    fn run<B: AutodiffBackend>(self, device: B::Device) -> Self::Output;
+}
+
+impl BackendKind {
+    pub const ALL: [BackendKind; 5] = [
+        BackendKind::NdArray,
+        BackendKind::Candle,
+        BackendKind::LibTorch,
+        BackendKind::Wgpu,
+        BackendKind::Metal,
+    ];
+
+    /// Reads the backend from [BACKEND_ENV], the default backend is used if it is not set.
+    pub fn from_env() -> Result<Self, std::io::Error> {
+        match std::env::var(BACKEND_ENV) {
+            Ok(name) => name.parse(),
+            Err(_) => Ok(Self::default()),
+        }
+    }
+
+    /// Name of the cargo feature which compiles the backend in.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            BackendKind::NdArray => "ndarray",
+            BackendKind::Candle => "candle",
+            BackendKind::LibTorch => "tch",
+            BackendKind::Wgpu => "wgpu",
+            BackendKind::Metal => "metal",
+        }
+    }
+
+    /// Whether the cargo feature of the backend is enabled.
+    pub fn is_compiled(&self) -> bool {
+        match self {
+            BackendKind::NdArray => cfg!(feature = "ndarray"),
+            BackendKind::Candle => cfg!(feature = "candle"),
+            BackendKind::LibTorch => cfg!(feature = "tch"),
+            BackendKind::Wgpu => cfg!(feature = "wgpu"),
+            BackendKind::Metal => cfg!(feature = "metal"),
+        }
+    }
+
+    /// Backends the binaries can run on with the enabled cargo features.
+    pub fn compiled() -> Vec<BackendKind> {
+        Self::ALL
+            .into_iter()
+            .filter(BackendKind::is_compiled)
+            .collect()
+    }
+
+    fn not_compiled(&self) -> std::io::Error {
+        let compiled = Self::compiled()
+            .iter()
+            .map(BackendKind::to_string)
+            .collect::<Vec<_>>();
+        std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!(
+                "Backend {self} is not compiled in, enable the {} feature or use one of: {}",
+                self.feature(),
+                compiled.join(", ")
+            ),
+        )
+    }
+
+    /// Runs the task on the default device of the backend.
+    /// Fails with [std::io::ErrorKind::Unsupported] if the backend is not compiled in.
+    #[allow(unreachable_patterns)]
+    pub fn run<T: BackendTask>(self, task: T) -> Result<T::Output, std::io::Error> {
+        Ok(match self {
+            #[cfg(feature = "ndarray")]
+            BackendKind::NdArray => {
+                task.run::<backend::Autodiff<backend::NdArray<f32>>>(Default::default())
+            }
+            #[cfg(feature = "candle")]
+            BackendKind::Candle => {
+                task.run::<backend::Autodiff<backend::Candle<f32, i64>>>(Default::default())
+            }
+            #[cfg(feature = "tch")]
+            BackendKind::LibTorch => {
+                task.run::<backend::Autodiff<backend::LibTorch<f32>>>(Default::default())
+            }
+            #[cfg(feature = "wgpu")]
+            BackendKind::Wgpu => {
+                task.run::<backend::Autodiff<backend::Wgpu<f32, i32>>>(Default::default())
+            }
+            #[cfg(feature = "metal")]
+            BackendKind::Metal => {
+                task.run::<backend::Autodiff<backend::Metal<f32, i32>>>(Default::default())
+            }
+            kind => return Err(kind.not_compiled()),
+        })
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = std::io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "ndarray" => Ok(BackendKind::NdArray),
+            "candle" => Ok(BackendKind::Candle),
+            "tch" | "libtorch" => Ok(BackendKind::LibTorch),
+            "wgpu" => Ok(BackendKind::Wgpu),
+            "metal" => Ok(BackendKind::Metal),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown backend {name}, expected ndarray, candle, tch, wgpu or metal"),
+            )),
+        }
+    }
+}
+
+impl Display for BackendKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.feature())
+    }
+}
@@ -0,0 +1,459 @@
+use std::{fs::File, path::Path};
+
+use burn::prelude::Backend;
+use chrono::{DateTime, NaiveDateTime};
+use csv::{ReaderBuilder, StringRecord, Writer};
+use polars::prelude::*;
+use taxifare_features::{TimeFeatures, haversine_distance};
+
+use crate::{
+    dataset::{mapped_dataset::RawDatafieldToFeaturesMapper, raw_dataset::TaxifareDatasetRawItem},
+    inference::{load_model, predict},
+    models::taxifare_model::Model,
+};
+
+const PREDICTED_FARE_COLUMN: &str = "predicted_fare";
+/// Fare amount minus the predicted fare.
+const RESIDUAL_COLUMN: &str = "residual";
+const ABSOLUTE_ERROR_COLUMN: &str = "absolute_error";
+/// Format of `pickup_datetime` in the raw NYC taxi fares file.
+const RAW_DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S UTC";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileFormat {
+    Csv,
+    Parquet,
+}
+
+impl FileFormat {
+    /// Parquet for `.parquet` and `.pq` files, csv otherwise.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("parquet" | "pq") => FileFormat::Parquet,
+            _ => FileFormat::Csv,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchPredictionOptions {
+    /// Rows which are read, predicted and written at once.
+    pub chunk_size: usize,
+    /// Adds the residual and absolute error, requires a `fare_amount` column.
+    pub with_errors: bool,
+}
+
+impl Default for BatchPredictionOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: 65_536,
+            with_errors: false,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BatchPredictionSummary {
+    pub rows: usize,
+    /// Rows with invalid or missing inputs get an empty prediction.
+    pub predicted: usize,
+}
+
+///
+/// Scores a raw or prepared trip file and writes its columns plus `predicted_fare`.
+/// Raw files have a `pickup_datetime` column and get the features of `taxifare_features`,
+/// prepared files already have the feature columns of the training data.
+/// The file is processed in chunks, so it does not have to fit into memory.
+/// The output has the format of the input.
+///
+pub fn predict_file<B: Backend>(
+    artifact_dir: &str,
+    input_file: &str,
+    output_file: &str,
+    options: BatchPredictionOptions,
+    device: B::Device,
+) -> Result<BatchPredictionSummary, std::io::Error> {
+    let format = FileFormat::from_path(input_file);
+    if FileFormat::from_path(output_file) != format {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("{output_file} must have the same format as {input_file}"),
+        ));
+    }
+    let (config, model, mapper) = load_model::<B>(artifact_dir, &device)?;
+    let predictor = ChunkPredictor {
+        model,
+        mapper,
+        batch_size: config.batch_size,
+        device,
+    };
+    match format {
+        FileFormat::Csv => predict_csv(&predictor, input_file, output_file, options),
+        FileFormat::Parquet => predict_parquet(&predictor, input_file, output_file, options)
+            .map_err(std::io::Error::other),
+    }
+}
+
+struct ChunkPredictor<B: Backend> {
+    model: Model<B>,
+    mapper: RawDatafieldToFeaturesMapper,
+    batch_size: usize,
+    device: B::Device,
+}
+
+impl<B: Backend> ChunkPredictor<B> {
+    /// Predicts the valid items of a chunk through a data loader.
+    fn predict(&self, items: &[Option<TaxifareDatasetRawItem>]) -> Vec<Option<f32>> {
+        let valid = items.iter().flatten().cloned().collect::<Vec<_>>();
+        let mut fares = if valid.is_empty() {
+            Vec::new()
+        } else {
+            predict(
+                &self.model,
+                &self.mapper,
+                valid.into_iter(),
+                self.batch_size,
+                &self.device,
+            )
+        }
+        .into_iter();
+        items
+            .iter()
+            .map(|item| item.as_ref().and_then(|_| fares.next()))
+            .collect()
+    }
+}
+
+/// Residual and absolute error of a prediction.
+fn prediction_errors(fare_amount: Option<f64>, predicted: Option<f32>) -> Option<(f64, f64)> {
+    let residual = fare_amount? - predicted? as f64;
+    Some((residual, residual.abs()))
+}
+
+fn predict_csv<B: Backend>(
+    predictor: &ChunkPredictor<B>,
+    input_file: &str,
+    output_file: &str,
+    options: BatchPredictionOptions,
+) -> Result<BatchPredictionSummary, std::io::Error> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(input_file)?;
+    let headers = reader.headers()?.clone();
+    let columns = TripColumns::new(headers.iter(), options.with_errors)?;
+
+    let mut writer = Writer::from_path(output_file)?;
+    let mut output_headers = headers.clone();
+    output_headers.push_field(PREDICTED_FARE_COLUMN);
+    if options.with_errors {
+        output_headers.push_field(RESIDUAL_COLUMN);
+        output_headers.push_field(ABSOLUTE_ERROR_COLUMN);
+    }
+    writer.write_record(&output_headers)?;
+
+    let optional = |value: Option<String>| value.unwrap_or_default();
+    let mut summary = BatchPredictionSummary::default();
+    let mut records = reader.into_records();
+    loop {
+        let chunk = records
+            .by_ref()
+            .take(options.chunk_size.max(1))
+            .collect::<Result<Vec<StringRecord>, _>>()?;
+        if chunk.is_empty() {
+            break;
+        }
+        let items = chunk
+            .iter()
+            .map(|record| columns.item(record))
+            .collect::<Vec<_>>();
+        let fares = predictor.predict(&items);
+
+        for (mut record, fare) in chunk.into_iter().zip(fares) {
+            summary.rows += 1;
+            summary.predicted += fare.is_some() as usize;
+            let errors = prediction_errors(columns.fare_amount(&record), fare);
+            record.push_field(&optional(fare.map(|fare| fare.to_string())));
+            if options.with_errors {
+                record.push_field(&optional(errors.map(|(residual, _)| residual.to_string())));
+                record.push_field(&optional(errors.map(|(_, error)| error.to_string())));
+            }
+            writer.write_record(&record)?;
+        }
+    }
+    writer.flush()?;
+    Ok(summary)
+}
+
+fn predict_parquet<B: Backend>(
+    predictor: &ChunkPredictor<B>,
+    input_file: &str,
+    output_file: &str,
+    options: BatchPredictionOptions,
+) -> PolarsResult<BatchPredictionSummary> {
+    let input_schema =
+        LazyFrame::scan_parquet(input_file, ScanArgsParquet::default())?.collect_schema()?;
+    let columns = TripColumns::new(
+        input_schema.iter_names().map(|name| name.as_str()),
+        options.with_errors,
+    )?;
+
+    let mut output_schema = (*input_schema).clone();
+    output_schema.with_column(PREDICTED_FARE_COLUMN.into(), DataType::Float32);
+    if options.with_errors {
+        output_schema.with_column(RESIDUAL_COLUMN.into(), DataType::Float64);
+        output_schema.with_column(ABSOLUTE_ERROR_COLUMN.into(), DataType::Float64);
+    }
+    let mut writer = ParquetWriter::new(File::create(output_file)?).batched(&output_schema)?;
+
+    // A single pass over the file, the row groups are split into chunks.
+    let mut reader =
+        ParquetReader::new(File::open(input_file)?).batched(options.chunk_size.max(1))?;
+    let runtime = polars::io::pl_async::get_runtime();
+    let mut summary = BatchPredictionSummary::default();
+    while let Some(chunks) = runtime.block_on(reader.next_batches(1))? {
+        for mut df in chunks {
+            predict_frame(predictor, &columns, &mut df, options, &mut summary)?;
+            writer.write_batch(&df)?;
+        }
+    }
+    writer.finish()?;
+    Ok(summary)
+}
+
+/// Adds the prediction columns to a chunk of a parquet file.
+fn predict_frame<B: Backend>(
+    predictor: &ChunkPredictor<B>,
+    columns: &TripColumns,
+    df: &mut DataFrame,
+    options: BatchPredictionOptions,
+    summary: &mut BatchPredictionSummary,
+) -> PolarsResult<()> {
+    let rows = (0..df.height())
+        .map(|row| FrameRow {
+            columns: df.get_columns(),
+            row,
+        })
+        .collect::<Vec<_>>();
+    let items = rows.iter().map(|row| columns.item(row)).collect::<Vec<_>>();
+    let fares = predictor.predict(&items);
+    let errors = rows
+        .iter()
+        .zip(&fares)
+        .map(|(row, fare)| prediction_errors(columns.fare_amount(row), *fare))
+        .collect::<Vec<_>>();
+
+    summary.rows += df.height();
+    summary.predicted += fares.iter().flatten().count();
+    df.with_column(Column::new(PREDICTED_FARE_COLUMN.into(), fares))?;
+    if options.with_errors {
+        let residuals = errors
+            .iter()
+            .map(|errors| errors.map(|(residual, _)| residual));
+        let absolute_errors = errors.iter().map(|errors| errors.map(|(_, error)| error));
+        df.with_column(Column::new(
+            RESIDUAL_COLUMN.into(),
+            residuals.collect::<Vec<_>>(),
+        ))?;
+        df.with_column(Column::new(
+            ABSOLUTE_ERROR_COLUMN.into(),
+            absolute_errors.collect::<Vec<_>>(),
+        ))?;
+    }
+    Ok(())
+}
+
+/// Values of one input row, looked up by column index.
+trait TripRow {
+    fn number(&self, column: usize) -> Option<f64>;
+    fn timestamp_millis(&self, column: usize) -> Option<i64>;
+}
+
+/// Accepts RFC 3339 and the format of the raw NYC taxi fares file.
+fn parse_datetime_millis(text: &str) -> Option<i64> {
+    let text = text.trim();
+    DateTime::parse_from_rfc3339(text)
+        .map(|datetime| datetime.timestamp_millis())
+        .or_else(|_| {
+            NaiveDateTime::parse_from_str(text, RAW_DATETIME_FORMAT)
+                .map(|datetime| datetime.and_utc().timestamp_millis())
+        })
+        .ok()
+}
+
+impl TripRow for StringRecord {
+    fn number(&self, column: usize) -> Option<f64> {
+        self.get(column)?.trim().parse().ok()
+    }
+
+    fn timestamp_millis(&self, column: usize) -> Option<i64> {
+        parse_datetime_millis(self.get(column)?)
+    }
+}
+
+struct FrameRow<'a> {
+    columns: &'a [Column],
+    row: usize,
+}
+
+impl TripRow for FrameRow<'_> {
+    /// Datetimes are read as seconds since the unix epoch, the unit of `pickup_timestamp`.
+    fn number(&self, column: usize) -> Option<f64> {
+        match self.columns[column].get(self.row).ok()? {
+            AnyValue::Datetime(..) => self
+                .timestamp_millis(column)
+                .map(|millis| millis.div_euclid(1000) as f64),
+            value => value.extract(),
+        }
+    }
+
+    fn timestamp_millis(&self, column: usize) -> Option<i64> {
+        match self.columns[column].get(self.row).ok()? {
+            AnyValue::Datetime(value, unit, _) => Some(match unit {
+                TimeUnit::Nanoseconds => value.div_euclid(1_000_000),
+                TimeUnit::Microseconds => value.div_euclid(1_000),
+                TimeUnit::Milliseconds => value,
+            }),
+            AnyValue::String(text) => parse_datetime_millis(text),
+            _ => None,
+        }
+    }
+}
+
+/// Columns of the time based and distance features.
+enum FeatureColumns {
+    /// Features are computed from the pickup time and the coordinates.
+    Raw { pickup_datetime: usize },
+    /// Features computed by `data_preparation`.
+    Prepared {
+        distance: usize,
+        pickup_hour: usize,
+        pickup_weekday: usize,
+        am_or_pm: usize,
+        pickup_timestamp: Option<usize>,
+    },
+}
+
+struct TripColumns {
+    fare_amount: Option<usize>,
+    pickup_latitude: usize,
+    pickup_longitude: usize,
+    dropoff_latitude: usize,
+    dropoff_longitude: usize,
+    passenger_count: Option<usize>,
+    features: FeatureColumns,
+}
+
+impl TripColumns {
+    fn new<'a>(
+        headers: impl Iterator<Item = &'a str>,
+        with_errors: bool,
+    ) -> Result<Self, std::io::Error> {
+        let headers = headers.collect::<Vec<_>>();
+        let position = |name: &str| headers.iter().position(|header| *header == name);
+        let required = |name: &str| {
+            position(name).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Column {name} does not exist"),
+                )
+            })
+        };
+
+        let features = match position("distance") {
+            Some(distance) => FeatureColumns::Prepared {
+                distance,
+                pickup_hour: required("pickup_hour")?,
+                pickup_weekday: required("pickup_weekday")?,
+                am_or_pm: required("am_or_pm")?,
+                pickup_timestamp: position("pickup_timestamp"),
+            },
+            None => FeatureColumns::Raw {
+                pickup_datetime: required("pickup_datetime")?,
+            },
+        };
+        Ok(Self {
+            fare_amount: if with_errors {
+                Some(required("fare_amount")?)
+            } else {
+                position("fare_amount")
+            },
+            pickup_latitude: required("pickup_latitude")?,
+            pickup_longitude: required("pickup_longitude")?,
+            dropoff_latitude: required("dropoff_latitude")?,
+            dropoff_longitude: required("dropoff_longitude")?,
+            passenger_count: position("passenger_count"),
+            features,
+        })
+    }
+
+    fn fare_amount(&self, row: &impl TripRow) -> Option<f64> {
+        row.number(self.fare_amount?)
+    }
+
+    /// `None` if a required value is missing or invalid.
+    fn item(&self, row: &impl TripRow) -> Option<TaxifareDatasetRawItem> {
+        let category = |column| {
+            row.number(column)
+                .filter(|value| (0.0..=u8::MAX as f64).contains(value))
+                .map(|value| value as u8)
+        };
+        let pickup_latitude = row.number(self.pickup_latitude)?;
+        let pickup_longitude = row.number(self.pickup_longitude)?;
+        let dropoff_latitude = row.number(self.dropoff_latitude)?;
+        let dropoff_longitude = row.number(self.dropoff_longitude)?;
+
+        let (distance, pickup_hour, pickup_weekday, am_or_pm, pickup_timestamp) = match self
+            .features
+        {
+            FeatureColumns::Raw { pickup_datetime } => {
+                let time =
+                    TimeFeatures::from_timestamp_millis(row.timestamp_millis(pickup_datetime)?)?;
+                (
+                    Some(haversine_distance(
+                        pickup_latitude,
+                        pickup_longitude,
+                        dropoff_latitude,
+                        dropoff_longitude,
+                    )),
+                    time.pickup_hour,
+                    time.pickup_weekday,
+                    time.am_or_pm,
+                    Some(time.pickup_timestamp),
+                )
+            }
+            FeatureColumns::Prepared {
+                distance,
+                pickup_hour,
+                pickup_weekday,
+                am_or_pm,
+                pickup_timestamp,
+            } => (
+                row.number(distance),
+                category(pickup_hour)?,
+                category(pickup_weekday)?,
+                category(am_or_pm)?,
+                pickup_timestamp
+                    .and_then(|column| row.number(column))
+                    .map(|timestamp| timestamp as i64),
+            ),
+        };
+
+        Some(TaxifareDatasetRawItem {
+            fare_amount: self.fare_amount(row).unwrap_or_default(),
+            pickup_latitude,
+            pickup_longitude,
+            dropoff_latitude,
+            dropoff_longitude,
+            passenger_count: self.passenger_count.and_then(|column| row.number(column)),
+            distance,
+            pickup_hour,
+            pickup_weekday,
+            am_or_pm,
+            pickup_timestamp,
+            sample_weight: None,
+        })
+    }
+}
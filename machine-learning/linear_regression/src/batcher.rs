@@ -1,15 +1,145 @@
 use burn::{data::dataloader::batcher::Batcher, prelude::*};
 
-use super::dataset::mapped_dataset::TaxifareDatasetMappedItem;
+use super::dataset::mapped_dataset::{
+    NUM_CATEGORICAL_FEATURES, NUM_CONTINUOUS_FEATURES, TaxifareDatasetFeaturesItem,
+    TaxifareDatasetMappedItem,
+};
 
 #[derive(Clone, Default, Debug)]
 pub struct TaxifareBatcher;
 
+/// # Shapes
+///   - cont_features [batch_size, 6]
+///   - cont_mask [batch_size, 6], 1 where the continuous feature is present
+///   - cat_features [batch_size, 1] per categorical feature
+///   - predictions [batch_size, 1]
+///   - weights [batch_size, 1]
 #[derive(Clone, Debug)]
 pub struct TaxifareBatch<B: Backend> {
     pub cont_features: Tensor<B, 2>,
+    pub cont_mask: Tensor<B, 2>,
     pub cat_features: Vec<Tensor<B, 2, Int>>,
     pub predictions: Tensor<B, 2>,
+    /// Per item loss weights, `None` if the items are not weighted.
+    pub weights: Option<Tensor<B, 2>>,
+}
+
+/// Batches unlabeled items for prediction.
+#[derive(Clone, Default, Debug)]
+pub struct TaxifareInferenceBatcher;
+
+/// # Shapes
+///   - cont_features [batch_size, 6]
+///   - cont_mask [batch_size, 6], 1 where the continuous feature is present
+///   - cat_features [batch_size, 1] per categorical feature
+#[derive(Clone, Debug)]
+pub struct TaxifareInferenceBatch<B: Backend> {
+    pub cont_features: Tensor<B, 2>,
+    pub cont_mask: Tensor<B, 2>,
+    pub cat_features: Vec<Tensor<B, 2, Int>>,
+}
+
+/// Model inputs shared by labeled and unlabeled items.
+trait FeatureItem {
+    fn categorical_features(&self) -> [u8; NUM_CATEGORICAL_FEATURES];
+    fn continuous_features(&self) -> [f64; NUM_CONTINUOUS_FEATURES];
+    fn continuous_mask(&self) -> [bool; NUM_CONTINUOUS_FEATURES];
+}
+
+impl FeatureItem for TaxifareDatasetMappedItem {
+    fn categorical_features(&self) -> [u8; NUM_CATEGORICAL_FEATURES] {
+        [
+            self.discrete_weekday,
+            self.discrete_hour,
+            self.discrete_am_or_pm,
+        ]
+    }
+
+    fn continuous_features(&self) -> [f64; NUM_CONTINUOUS_FEATURES] {
+        self.continuous_features
+    }
+
+    fn continuous_mask(&self) -> [bool; NUM_CONTINUOUS_FEATURES] {
+        self.continuous_mask
+    }
+}
+
+impl FeatureItem for TaxifareDatasetFeaturesItem {
+    fn categorical_features(&self) -> [u8; NUM_CATEGORICAL_FEATURES] {
+        [
+            self.discrete_weekday,
+            self.discrete_hour,
+            self.discrete_am_or_pm,
+        ]
+    }
+
+    fn continuous_features(&self) -> [f64; NUM_CONTINUOUS_FEATURES] {
+        self.continuous_features
+    }
+
+    fn continuous_mask(&self) -> [bool; NUM_CONTINUOUS_FEATURES] {
+        self.continuous_mask
+    }
+}
+
+/// Creates the whole `[rows, columns]` tensor from row major values with a single allocation.
+fn float_tensor<B: Backend>(
+    values: Vec<f64>,
+    shape: [usize; 2],
+    device: &B::Device,
+) -> Tensor<B, 2> {
+    Tensor::from_data(
+        TensorData::new(values, shape).convert::<B::FloatElem>(),
+        device,
+    )
+}
+
+fn int_tensor<B: Backend>(
+    values: Vec<i64>,
+    shape: [usize; 2],
+    device: &B::Device,
+) -> Tensor<B, 2, Int> {
+    Tensor::from_data(
+        TensorData::new(values, shape).convert::<B::IntElem>(),
+        device,
+    )
+}
+
+/// The model inputs of the items, shared by the labeled and the unlabeled batch.
+fn feature_tensors<B: Backend, I: FeatureItem>(
+    items: &[I],
+    device: &B::Device,
+) -> TaxifareInferenceBatch<B> {
+    let batch_size = items.len();
+
+    let cont_features = items
+        .iter()
+        .flat_map(|item| item.continuous_features())
+        .collect();
+    let cont_features = float_tensor(cont_features, [batch_size, NUM_CONTINUOUS_FEATURES], device);
+
+    let cont_mask = items
+        .iter()
+        .flat_map(|item| item.continuous_mask())
+        .map(|present| if present { 1.0 } else { 0.0 })
+        .collect();
+    let cont_mask = float_tensor(cont_mask, [batch_size, NUM_CONTINUOUS_FEATURES], device);
+
+    let cat_features = (0..NUM_CATEGORICAL_FEATURES)
+        .map(|feature| {
+            let values = items
+                .iter()
+                .map(|item| item.categorical_features()[feature] as i64)
+                .collect();
+            int_tensor(values, [batch_size, 1], device)
+        })
+        .collect();
+
+    TaxifareInferenceBatch {
+        cont_features,
+        cont_mask,
+        cat_features,
+    }
 }
 
 impl<B: Backend> Batcher<B, TaxifareDatasetMappedItem, TaxifareBatch<B>> for TaxifareBatcher {
@@ -18,49 +148,38 @@ impl<B: Backend> Batcher<B, TaxifareDatasetMappedItem, TaxifareBatch<B>> for Tax
         items: Vec<TaxifareDatasetMappedItem>,
         device: &<B as Backend>::Device,
     ) -> TaxifareBatch<B> {
-        let cont_features = items
-            .iter()
-            .map(|item| TensorData::from(item.continuous_features).convert::<B::FloatElem>())
-            .map(|data| Tensor::<B, 1>::from_data(data, device))
-            .map(|tensor| tensor.reshape([1, 6]))
-            .collect();
-        let cat_weekday = items
-            .iter()
-            .map(|item| TensorData::from([item.discrete_weekday]).convert::<B::IntElem>())
-            .map(|data| Tensor::<B, 1, Int>::from_data(data, device))
-            .map(|tensor| tensor.reshape([1, 4]))
-            .collect();
-        let cat_weekday = Tensor::cat(cat_weekday, 1);
-
-        let cat_hour = items
-            .iter()
-            .map(|item| TensorData::from([item.discrete_hour]).convert::<B::IntElem>())
-            .map(|data| Tensor::<B, 1, Int>::from_data(data, device))
-            .map(|tensor| tensor.reshape([1, 12]))
-            .collect();
-        let cat_hour = Tensor::cat(cat_hour, 1);
-
-        let cat_am_or_pm = items
-            .iter()
-            .map(|item| TensorData::from([item.discrete_am_or_pm]).convert::<B::IntElem>())
-            .map(|data| Tensor::<B, 1, Int>::from_data(data, device))
-            .map(|tensor| tensor.reshape([1, 1]))
-            .collect();
-        let cat_am_or_pm = Tensor::cat(cat_am_or_pm, 1);
-
-        let predictions = items
-            .iter()
-            .map(|item| Tensor::<B, 1>::from_data([item.label], device))
-            .map(|tensor| tensor.reshape([1, 1]))
-            .collect();
-
-        let cont_features = Tensor::cat(cont_features, 2);
-        let cat_features = vec![cat_weekday, cat_hour, cat_am_or_pm];
-        let predictions = Tensor::cat(predictions, 1);
+        let batch_size = items.len();
+        let features = feature_tensors(&items, device);
+
+        let predictions = items.iter().map(|item| item.label).collect();
+        let predictions = float_tensor(predictions, [batch_size, 1], device);
+
+        let weights = items.iter().any(|item| item.weight.is_some()).then(|| {
+            let weights = items
+                .iter()
+                .map(|item| item.weight.unwrap_or(1.0))
+                .collect();
+            float_tensor(weights, [batch_size, 1], device)
+        });
+
         TaxifareBatch {
-            cont_features,
-            cat_features,
+            cont_features: features.cont_features,
+            cont_mask: features.cont_mask,
+            cat_features: features.cat_features,
             predictions,
+            weights,
         }
     }
 }
+
+impl<B: Backend> Batcher<B, TaxifareDatasetFeaturesItem, TaxifareInferenceBatch<B>>
+    for TaxifareInferenceBatcher
+{
+    fn batch(
+        &self,
+        items: Vec<TaxifareDatasetFeaturesItem>,
+        device: &<B as Backend>::Device,
+    ) -> TaxifareInferenceBatch<B> {
+        feature_tensors(&items, device)
+    }
+}
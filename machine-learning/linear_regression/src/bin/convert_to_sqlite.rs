@@ -0,0 +1,15 @@
+use linear_regression::dataset::raw_dataset::convert_csv_to_sqlite;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let csv_file = args
+        .next()
+        .unwrap_or_else(|| "TaxiFaresPrepared.csv".to_string());
+    let db_file = args
+        .next()
+        .unwrap_or_else(|| "TaxiFaresPrepared.db".to_string());
+    let weight_column = args.next();
+    let count = convert_csv_to_sqlite(&csv_file, &db_file, weight_column.as_deref())
+        .expect("Can not convert csv file.");
+    println!("Converted {count} items from {csv_file} into {db_file}");
+}
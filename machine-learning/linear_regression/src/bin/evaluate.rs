@@ -0,0 +1,112 @@
+use burn::tensor::backend::AutodiffBackend;
+use linear_regression::{
+    backend::{BackendKind, BackendTask},
+    evaluation::{EvaluationReport, EvaluationSplit, evaluate},
+};
+
+const USAGE: &str = "Usage: evaluate <artifact-dir> [--data <file>] [--split all|train|test] \
+[--output <dir>]
+
+  --data    Prepared csv file or sqlite database, defaults to TaxiFaresPrepared.csv.
+  --split   Items to evaluate, the train and test splits are the ones of the training run.
+  --output  Directory for evaluation_<split>.json and .csv, defaults to the artifact directory.
+
+The backend is selected with the TAXIFARE_BACKEND environment variable.";
+
+struct Args {
+    artifact_dir: String,
+    data_file: String,
+    split: EvaluationSplit,
+    output_dir: Option<String>,
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut artifact_dir = None;
+    let mut parsed = Args {
+        artifact_dir: String::new(),
+        data_file: "TaxiFaresPrepared.csv".to_string(),
+        split: EvaluationSplit::All,
+        output_dir: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with_usage(&format!("Missing value of {arg}")))
+        };
+        match arg.as_str() {
+            "--data" => parsed.data_file = value(),
+            "--split" => {
+                parsed.split = value()
+                    .parse()
+                    .unwrap_or_else(|err: std::io::Error| exit_with_usage(&err.to_string()))
+            }
+            "--output" => parsed.output_dir = Some(value()),
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if artifact_dir.is_none() && !arg.starts_with('-') => artifact_dir = Some(arg),
+            _ => exit_with_usage(&format!("Unknown argument {arg}")),
+        }
+    }
+    parsed.artifact_dir =
+        artifact_dir.unwrap_or_else(|| exit_with_usage("Missing artifact directory"));
+    parsed
+}
+
+struct Evaluation {
+    args: Args,
+}
+
+impl BackendTask for Evaluation {
+    type Output = Result<EvaluationReport, std::io::Error>;
+
+    fn run<B: AutodiffBackend>(self, device: B::Device) -> Self::Output {
+        // Without autodiff dropout is disabled and batch norm uses its running statistics
+        evaluate::<B::InnerBackend>(
+            &self.args.artifact_dir,
+            &self.args.data_file,
+            self.args.split,
+            device,
+        )
+    }
+}
+
+fn main() {
+    let args = parse_args();
+    let output_dir = args
+        .output_dir
+        .clone()
+        .unwrap_or_else(|| args.artifact_dir.clone());
+
+    let backend = BackendKind::from_env().expect("Backend can not be selected.");
+    let report = backend
+        .run(Evaluation { args })
+        .and_then(|report| report)
+        .unwrap_or_else(|err| {
+            eprintln!("Evaluation failed: {err}");
+            std::process::exit(1);
+        });
+    report
+        .save(&output_dir)
+        .expect("Evaluation report can not be saved.");
+
+    let overall = &report.overall;
+    println!(
+        "Evaluated {} items of split {}: RMSE {:.4}, MAE {:.4}, R2 {:.4}, MAPE {:.2} %, median AE {:.4}",
+        overall.count,
+        report.split,
+        overall.rmse,
+        overall.mae,
+        overall.r2,
+        overall.mape,
+        overall.median_ae
+    );
+    println!("Report written to {output_dir}");
+}
@@ -0,0 +1,115 @@
+use burn::tensor::backend::AutodiffBackend;
+use linear_regression::{
+    backend::{BackendKind, BackendTask},
+    batch_prediction::{BatchPredictionOptions, BatchPredictionSummary, predict_file},
+};
+
+const USAGE: &str = "Usage: predict <artifact-dir> <input-file> [--output <file>] \
+[--chunk-size <rows>] [--with-errors]
+
+  --output       Defaults to <input-file> with a _predictions suffix.
+  --chunk-size   Rows read, predicted and written at once, defaults to 65536.
+  --with-errors  Adds residual and absolute_error columns, requires fare_amount.
+
+The input is a raw trip file with pickup_datetime or a prepared file with the feature columns,
+as csv or parquet. The output has the format of the input.
+The backend is selected with the TAXIFARE_BACKEND environment variable.";
+
+struct Args {
+    artifact_dir: String,
+    input_file: String,
+    output_file: String,
+    options: BatchPredictionOptions,
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
+
+/// `trips.csv` becomes `trips_predictions.csv`.
+fn default_output_file(input_file: &str) -> String {
+    let path = std::path::Path::new(input_file);
+    let stem = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("trips");
+    let file_name = match path.extension().and_then(|extension| extension.to_str()) {
+        Some(extension) => format!("{stem}_predictions.{extension}"),
+        None => format!("{stem}_predictions"),
+    };
+    path.with_file_name(file_name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn parse_args() -> Args {
+    let mut positional = Vec::new();
+    let mut output_file = None;
+    let mut options = BatchPredictionOptions::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with_usage(&format!("Missing value of {arg}")))
+        };
+        match arg.as_str() {
+            "--output" => output_file = Some(value()),
+            "--chunk-size" => {
+                options.chunk_size = value()
+                    .parse()
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .unwrap_or_else(|| exit_with_usage("Invalid value of --chunk-size"))
+            }
+            "--with-errors" => options.with_errors = true,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if positional.len() < 2 && !arg.starts_with('-') => positional.push(arg),
+            _ => exit_with_usage(&format!("Unknown argument {arg}")),
+        }
+    }
+    let [artifact_dir, input_file] = <[String; 2]>::try_from(positional)
+        .unwrap_or_else(|_| exit_with_usage("Missing artifact directory or input file"));
+    Args {
+        output_file: output_file.unwrap_or_else(|| default_output_file(&input_file)),
+        artifact_dir,
+        input_file,
+        options,
+    }
+}
+
+struct Prediction {
+    args: Args,
+}
+
+impl BackendTask for Prediction {
+    type Output = Result<BatchPredictionSummary, std::io::Error>;
+
+    fn run<B: AutodiffBackend>(self, device: B::Device) -> Self::Output {
+        predict_file::<B::InnerBackend>(
+            &self.args.artifact_dir,
+            &self.args.input_file,
+            &self.args.output_file,
+            self.args.options,
+            device,
+        )
+    }
+}
+
+fn main() {
+    let args = parse_args();
+    let output_file = args.output_file.clone();
+
+    let backend = BackendKind::from_env().expect("Backend can not be selected.");
+    let summary = backend
+        .run(Prediction { args })
+        .and_then(|summary| summary)
+        .expect("Trips can not be predicted.");
+    println!(
+        "Predicted {} of {} rows into {output_file}",
+        summary.predicted, summary.rows
+    );
+}
@@ -0,0 +1,83 @@
+use std::time::Duration;
+
+use burn::backend::{NdArray, ndarray::NdArrayDevice};
+use linear_regression::server::{ServerState, batching::BatchingConfig, router};
+use tokio::net::TcpListener;
+
+const USAGE: &str = "Usage: serve <artifact-dir> [--addr <host:port>] [--max-batch-size <trips>] \
+[--max-wait-ms <ms>]
+
+  --addr            Address to listen on, defaults to 127.0.0.1:3000.
+  --max-batch-size  Most trips predicted in one forward pass, defaults to 256.
+  --max-wait-ms     Longest time a request waits for concurrent requests, defaults to 5.
+
+Serves POST /predict, GET /health, GET /model and GET /metrics, the model runs on the CPU.";
+
+struct Args {
+    artifact_dir: String,
+    addr: String,
+    batching: BatchingConfig,
+}
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut artifact_dir = None;
+    let mut parsed = Args {
+        artifact_dir: String::new(),
+        addr: "127.0.0.1:3000".to_string(),
+        batching: BatchingConfig::default(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with_usage(&format!("Missing value of {arg}")))
+        };
+        match arg.as_str() {
+            "--addr" => parsed.addr = value(),
+            "--max-batch-size" => {
+                parsed.batching.max_batch_size = value()
+                    .parse()
+                    .ok()
+                    .filter(|size| *size > 0)
+                    .unwrap_or_else(|| exit_with_usage("Invalid value of --max-batch-size"))
+            }
+            "--max-wait-ms" => {
+                parsed.batching.max_wait = value()
+                    .parse()
+                    .map(Duration::from_millis)
+                    .unwrap_or_else(|_| exit_with_usage("Invalid value of --max-wait-ms"))
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if artifact_dir.is_none() && !arg.starts_with('-') => artifact_dir = Some(arg),
+            _ => exit_with_usage(&format!("Unknown argument {arg}")),
+        }
+    }
+    parsed.artifact_dir =
+        artifact_dir.unwrap_or_else(|| exit_with_usage("Missing artifact directory"));
+    parsed
+}
+
+#[tokio::main]
+async fn main() {
+    let args = parse_args();
+    let state = ServerState::load::<NdArray>(&args.artifact_dir, NdArrayDevice::Cpu, args.batching)
+        .expect("Model metadata can not be loaded.");
+    let listener = TcpListener::bind(&args.addr)
+        .await
+        .expect("Server address can not be bound.");
+    println!(
+        "Serving the model of {} on http://{}",
+        args.artifact_dir, args.addr
+    );
+    axum::serve(listener, router(state))
+        .await
+        .expect("Server can not be run.");
+}
@@ -1,3 +1,7 @@
+pub mod feature_cache;
 pub mod mapped_dataset;
-mod raw_dataset;
+pub mod raw_dataset;
+pub mod sample_weights;
+pub mod scaler;
+pub mod subset_dataset;
 pub mod taxifare_dataset;
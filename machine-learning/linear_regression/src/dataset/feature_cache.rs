@@ -0,0 +1,270 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use burn::data::dataset::Dataset;
+
+use super::mapped_dataset::{
+    NUM_CATEGORICAL_FEATURES, NUM_CONTINUOUS_FEATURES, TaxifareDatasetMappedItem,
+};
+
+const MAGIC: &[u8; 8] = b"TXFCACHE";
+/// Has to be increased whenever the layout of the file changes.
+const FORMAT_VERSION: u32 = 3;
+/// Magic bytes, version, key and row count.
+const HEADER_LEN: u64 = MAGIC.len() as u64 + 4 + 8 + 8;
+/// Continuous features as f64 and their masks, categorical indices and the label as f64.
+const ROW_LEN: u64 = (NUM_CONTINUOUS_FEATURES * 9 + NUM_CATEGORICAL_FEATURES + 8) as u64;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+///
+/// Stable 64 bit FNV-1a hash, used to key the cache on its inputs.
+/// The std hashers are not guaranteed to be stable between Rust releases.
+///
+#[derive(Clone, Copy, Debug)]
+pub struct CacheKey(u64);
+
+impl Default for CacheKey {
+    fn default() -> Self {
+        Self(FNV_OFFSET_BASIS)
+    }
+}
+
+impl CacheKey {
+    pub fn update(mut self, bytes: &[u8]) -> Self {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+        self
+    }
+
+    pub fn update_file(mut self, file_name: &str) -> Result<Self, std::io::Error> {
+        let mut reader = BufReader::new(File::open(file_name)?);
+        let mut buffer = vec![0; 1 << 16];
+        loop {
+            let read = reader.read(&mut buffer)?;
+            if read == 0 {
+                return Ok(self);
+            }
+            self = self.update(&buffer[..read]);
+        }
+    }
+}
+
+///
+/// Mapped features stored as contiguous arrays, one row per item in source order.
+/// The continuous features, their masks and the categorical indices are row major matrices.
+/// Weights are either stored for all items or for none, if only some items have a weight
+/// the others are weighted with 1.
+///
+pub struct FeatureCache {
+    continuous: Vec<f64>,
+    continuous_mask: Vec<bool>,
+    categorical: Vec<u8>,
+    labels: Vec<f64>,
+    weights: Option<Vec<f64>>,
+}
+
+impl FeatureCache {
+    pub fn from_dataset<D: Dataset<TaxifareDatasetMappedItem>>(dataset: &D) -> Self {
+        let mut continuous = Vec::with_capacity(dataset.len() * NUM_CONTINUOUS_FEATURES);
+        let mut continuous_mask = Vec::with_capacity(dataset.len() * NUM_CONTINUOUS_FEATURES);
+        let mut categorical = Vec::with_capacity(dataset.len() * NUM_CATEGORICAL_FEATURES);
+        let mut labels = Vec::with_capacity(dataset.len());
+        let mut weights = Vec::with_capacity(dataset.len());
+        let mut weighted = false;
+        for item in (0..dataset.len()).filter_map(|idx| dataset.get(idx)) {
+            continuous.extend_from_slice(&item.continuous_features);
+            continuous_mask.extend_from_slice(&item.continuous_mask);
+            categorical.extend_from_slice(&[
+                item.discrete_weekday,
+                item.discrete_hour,
+                item.discrete_am_or_pm,
+            ]);
+            labels.push(item.label);
+            weights.push(item.weight.unwrap_or(1.0));
+            weighted |= item.weight.is_some();
+        }
+        let weights = weighted.then_some(weights);
+        Self {
+            continuous,
+            continuous_mask,
+            categorical,
+            labels,
+            weights,
+        }
+    }
+
+    ///
+    /// Loads the cache if it exists and was written for the same key,
+    /// otherwise the cache is rebuilt from the dataset and written to the file.
+    /// A cache file which can not be read, e.g. because it is truncated, is rebuilt as well,
+    /// and a cache which can not be written is only kept in memory.
+    /// Both are returned as warnings next to the cache, the features are correct regardless.
+    ///
+    pub fn load_or_create<D: Dataset<TaxifareDatasetMappedItem>>(
+        cache_file: &Path,
+        key: CacheKey,
+        dataset: &D,
+    ) -> (Self, Vec<String>) {
+        let mut warnings = Vec::new();
+        match Self::load(cache_file, key) {
+            Ok(Some(cache)) => return (cache, warnings),
+            Ok(None) => {}
+            Err(err) => warnings.push(format!(
+                "Feature cache {} can not be read and is rebuilt: {err}",
+                cache_file.display()
+            )),
+        }
+        let cache = Self::from_dataset(dataset);
+        if let Err(err) = cache.save(cache_file, key) {
+            warnings.push(format!(
+                "Feature cache {} can not be written: {err}",
+                cache_file.display()
+            ));
+        }
+        (cache, warnings)
+    }
+
+    /// Returns `None` if there is no cache file or it is outdated,
+    /// and an error if the file is truncated or otherwise corrupt.
+    pub fn load(cache_file: &Path, key: CacheKey) -> Result<Option<Self>, std::io::Error> {
+        let Ok(file) = File::open(cache_file) else {
+            return Ok(None);
+        };
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        let mut magic = [0; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC
+            || u32::from_le_bytes(read_array(&mut reader)?) != FORMAT_VERSION
+            || u64::from_le_bytes(read_array(&mut reader)?) != key.0
+        {
+            return Ok(None);
+        }
+        let rows = u64::from_le_bytes(read_array(&mut reader)?);
+        // Checked before allocating, a corrupt row count could otherwise exhaust the memory
+        let len_without_weights = (HEADER_LEN + 1).saturating_add(rows.saturating_mul(ROW_LEN));
+        let weights_len = rows.saturating_mul(size_of::<f64>() as u64);
+        if file_len != len_without_weights
+            && file_len != len_without_weights.saturating_add(weights_len)
+        {
+            return Err(std::io::Error::new(
+                ErrorKind::InvalidData,
+                format!("{file_len} bytes do not fit {rows} rows"),
+            ));
+        }
+        let rows = rows as usize;
+
+        let continuous = read_f64s(&mut reader, rows * NUM_CONTINUOUS_FEATURES)?;
+        let mut continuous_mask = vec![0; rows * NUM_CONTINUOUS_FEATURES];
+        reader.read_exact(&mut continuous_mask)?;
+        let mut categorical = vec![0; rows * NUM_CATEGORICAL_FEATURES];
+        reader.read_exact(&mut categorical)?;
+        let labels = read_f64s(&mut reader, rows)?;
+        let weights = match read_array::<1>(&mut reader)? {
+            [0] => None,
+            _ => Some(read_f64s(&mut reader, rows)?),
+        };
+
+        Ok(Some(Self {
+            continuous,
+            continuous_mask: continuous_mask
+                .into_iter()
+                .map(|present| present != 0)
+                .collect(),
+            categorical,
+            labels,
+            weights,
+        }))
+    }
+
+    ///
+    /// Writes the cache to a temporary file which is renamed to `cache_file` once complete,
+    /// so an interrupted run never leaves a partial cache behind.
+    ///
+    pub fn save(&self, cache_file: &Path, key: CacheKey) -> Result<(), std::io::Error> {
+        let mut temp_file = cache_file.as_os_str().to_owned();
+        temp_file.push(format!(".{}.tmp", std::process::id()));
+        let temp_file = PathBuf::from(temp_file);
+        let result = self
+            .write(&temp_file, key)
+            .and_then(|_| std::fs::rename(&temp_file, cache_file));
+        if result.is_err() {
+            std::fs::remove_file(&temp_file).ok();
+        }
+        result
+    }
+
+    fn write(&self, file: &Path, key: CacheKey) -> Result<(), std::io::Error> {
+        let mut writer = BufWriter::new(File::create(file)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&key.0.to_le_bytes())?;
+        writer.write_all(&(self.labels.len() as u64).to_le_bytes())?;
+        for value in &self.continuous {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        let continuous_mask = self
+            .continuous_mask
+            .iter()
+            .map(|present| *present as u8)
+            .collect::<Vec<_>>();
+        writer.write_all(&continuous_mask)?;
+        writer.write_all(&self.categorical)?;
+        for value in &self.labels {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.write_all(&[self.weights.is_some() as u8])?;
+        for value in self.weights.iter().flatten() {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+        writer.into_inner()?.sync_all()
+    }
+}
+
+fn read_array<const N: usize>(reader: &mut impl Read) -> Result<[u8; N], std::io::Error> {
+    let mut bytes = [0; N];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes)
+}
+
+fn read_f64s(reader: &mut impl Read, len: usize) -> Result<Vec<f64>, std::io::Error> {
+    let mut bytes = vec![0; len * size_of::<f64>()];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes
+        .chunks_exact(size_of::<f64>())
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().unwrap()))
+        .collect())
+}
+
+impl Dataset<TaxifareDatasetMappedItem> for FeatureCache {
+    fn get(&self, index: usize) -> Option<TaxifareDatasetMappedItem> {
+        let label = *self.labels.get(index)?;
+        let continuous_range =
+            index * NUM_CONTINUOUS_FEATURES..(index + 1) * NUM_CONTINUOUS_FEATURES;
+        let continuous = &self.continuous[continuous_range.clone()];
+        let continuous_mask = &self.continuous_mask[continuous_range];
+        let categorical = &self.categorical
+            [index * NUM_CATEGORICAL_FEATURES..(index + 1) * NUM_CATEGORICAL_FEATURES];
+        Some(TaxifareDatasetMappedItem {
+            discrete_weekday: categorical[0],
+            discrete_hour: categorical[1],
+            discrete_am_or_pm: categorical[2],
+            continuous_features: continuous.try_into().unwrap(),
+            continuous_mask: continuous_mask.try_into().unwrap(),
+            label,
+            weight: self.weights.as_ref().map(|weights| weights[index]),
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.labels.len()
+    }
+}
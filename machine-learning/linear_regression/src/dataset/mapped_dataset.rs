@@ -1,6 +1,31 @@
-use burn::data::dataset::transform::{Mapper, MapperDataset};
+use std::{
+    fmt::Display,
+    io::{self, ErrorKind},
+};
 
-use super::raw_dataset::{TaxifareDatasetRawItem, TaxifareRawDataset};
+use burn::data::dataset::{
+    Dataset,
+    transform::{Mapper, MapperDataset},
+};
+use serde::Serialize;
+
+use super::{
+    feature_cache::CacheKey,
+    raw_dataset::{TaxifareDatasetRawItem, TaxifareSourceDataset},
+    sample_weights::SampleWeights,
+    scaler::FeatureScaler,
+};
+
+pub const NUM_CONTINUOUS_FEATURES: usize = 6;
+pub const NUM_CATEGORICAL_FEATURES: usize = 3;
+
+/// Column names of the categorical features in the order of the embeddings.
+pub const CATEGORICAL_FEATURE_NAMES: [&str; NUM_CATEGORICAL_FEATURES] =
+    ["pickup_weekday", "pickup_hour", "am_or_pm"];
+
+/// Largest supported cardinality of a categorical feature, the values are stored as `u8`
+/// and the unknown bucket takes the index right after the last known value.
+pub const MAX_CARDINALITY: usize = u8::MAX as usize;
 
 #[derive(Clone, Debug)]
 pub struct TaxifareDatasetMappedItem {
@@ -8,30 +33,188 @@ pub struct TaxifareDatasetMappedItem {
     pub discrete_hour: u8,
     pub discrete_am_or_pm: u8,
 
-    pub continuous_features: [f64; 6],
+    pub continuous_features: [f64; NUM_CONTINUOUS_FEATURES],
+    /// `false` where the continuous feature is missing, its value is then 0.
+    pub continuous_mask: [bool; NUM_CONTINUOUS_FEATURES],
     pub label: f64,
+    /// Contribution to the loss, `None` with uniform weighting.
+    pub weight: Option<f64>,
+}
+
+/// Model inputs of an item without a label, used for prediction.
+#[derive(Clone, Debug)]
+pub struct TaxifareDatasetFeaturesItem {
+    pub discrete_weekday: u8,
+    pub discrete_hour: u8,
+    pub discrete_am_or_pm: u8,
+
+    pub continuous_features: [f64; NUM_CONTINUOUS_FEATURES],
+    /// `false` where the continuous feature is missing, its value is then 0.
+    pub continuous_mask: [bool; NUM_CONTINUOUS_FEATURES],
+}
+
+/// Unscaled continuous features of a raw item in the order expected by the model.
+/// `None` marks a missing value.
+pub(crate) fn continuous_features(
+    item: &TaxifareDatasetRawItem,
+) -> [Option<f64>; NUM_CONTINUOUS_FEATURES] {
+    [
+        Some(item.pickup_latitude),
+        Some(item.pickup_longitude),
+        Some(item.dropoff_latitude),
+        Some(item.dropoff_longitude),
+        item.passenger_count,
+        item.distance,
+    ]
+}
+
+/// Raw categorical values of an item in the order of [CATEGORICAL_FEATURE_NAMES].
+fn categorical_features(item: &TaxifareDatasetRawItem) -> [u8; NUM_CATEGORICAL_FEATURES] {
+    [item.pickup_weekday, item.pickup_hour, item.am_or_pm]
+}
+
+/// A categorical value which has no row in the embedding of its feature.
+#[derive(Clone, Debug, Serialize)]
+pub struct CategoricalViolation {
+    /// 1-based data row of the source file, the header is not counted.
+    pub row: usize,
+    pub feature: &'static str,
+    pub value: u8,
+    pub cardinality: usize,
+}
+
+impl Display for CategoricalViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "row {}: {} = {} is outside of 0..{}",
+            self.row, self.feature, self.value, self.cardinality
+        )
+    }
 }
 
-pub struct RawDatafieldToFeaturesMapper;
+#[derive(Clone, Debug)]
+pub struct RawDatafieldToFeaturesMapper {
+    scaler: FeatureScaler,
+    cardinalities: [usize; NUM_CATEGORICAL_FEATURES],
+    sample_weights: SampleWeights,
+}
+
+impl RawDatafieldToFeaturesMapper {
+    /// The cardinalities are the number of known values per categorical feature,
+    /// see [ModelConfig::categorical_cardinalities](crate::models::taxifare_model::ModelConfig::categorical_cardinalities).
+    /// One cardinality per categorical feature is required, each at most [MAX_CARDINALITY],
+    /// otherwise an [ErrorKind::InvalidInput] error is returned.
+    pub fn new(scaler: FeatureScaler, cardinalities: &[usize]) -> Result<Self, io::Error> {
+        let cardinalities: [usize; NUM_CATEGORICAL_FEATURES] =
+            cardinalities.try_into().map_err(|_| {
+                io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!(
+                        "One embedding per categorical feature is required, got {} instead of {NUM_CATEGORICAL_FEATURES}",
+                        cardinalities.len()
+                    ),
+                )
+            })?;
+        if let Some((feature, cardinality)) = cardinalities
+            .iter()
+            .enumerate()
+            .find(|(_, cardinality)| **cardinality > MAX_CARDINALITY)
+        {
+            return Err(io::Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The embedding of {} has {cardinality} values, at most {MAX_CARDINALITY} are supported",
+                    CATEGORICAL_FEATURE_NAMES[feature]
+                ),
+            ));
+        }
+        Ok(Self {
+            scaler,
+            cardinalities,
+            sample_weights: SampleWeights::default(),
+        })
+    }
+
+    pub fn with_sample_weights(mut self, sample_weights: SampleWeights) -> Self {
+        self.sample_weights = sample_weights;
+        self
+    }
+
+    /// Extends the key with everything that influences the mapped features.
+    pub fn cache_key(&self, key: CacheKey) -> CacheKey {
+        let scaler = serde_json::to_vec(&self.scaler).expect("Scaler can be serialized.");
+        let sample_weights =
+            serde_json::to_vec(&self.sample_weights).expect("Sample weights can be serialized.");
+        self.cardinalities.iter().fold(
+            key.update(&scaler).update(&sample_weights),
+            |key, cardinality| key.update(&(*cardinality as u64).to_le_bytes()),
+        )
+    }
+
+    /// Values outside of the cardinality are mapped to the reserved unknown bucket,
+    /// which is the index right after the last known value.
+    fn bucket(&self, feature: usize, value: u8) -> u8 {
+        let cardinality = self.cardinalities[feature];
+        if (value as usize) < cardinality {
+            value
+        } else {
+            cardinality as u8
+        }
+    }
+
+    /// Scans a dataset in source order and reports every categorical value
+    /// which would end up in the unknown bucket.
+    pub fn categorical_violations<D: Dataset<TaxifareDatasetRawItem>>(
+        &self,
+        dataset: &D,
+    ) -> Vec<CategoricalViolation> {
+        (0..dataset.len())
+            .filter_map(|idx| dataset.get(idx).map(|item| (idx, item)))
+            .flat_map(|(idx, item)| {
+                categorical_features(&item)
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(feature, value)| (*value as usize) >= self.cardinalities[*feature])
+                    .map(move |(feature, value)| CategoricalViolation {
+                        row: idx + 1,
+                        feature: CATEGORICAL_FEATURE_NAMES[feature],
+                        value,
+                        cardinality: self.cardinalities[feature],
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+}
+
+impl Mapper<TaxifareDatasetRawItem, TaxifareDatasetFeaturesItem> for RawDatafieldToFeaturesMapper {
+    fn map(&self, item: &TaxifareDatasetRawItem) -> TaxifareDatasetFeaturesItem {
+        let continuous = continuous_features(item);
+        TaxifareDatasetFeaturesItem {
+            discrete_weekday: self.bucket(0, item.pickup_weekday),
+            discrete_hour: self.bucket(1, item.pickup_hour),
+            discrete_am_or_pm: self.bucket(2, item.am_or_pm),
+            continuous_features: self.scaler.transform(continuous),
+            continuous_mask: continuous.map(|value| value.is_some()),
+        }
+    }
+}
 
 impl Mapper<TaxifareDatasetRawItem, TaxifareDatasetMappedItem> for RawDatafieldToFeaturesMapper {
     fn map(&self, item: &TaxifareDatasetRawItem) -> TaxifareDatasetMappedItem {
+        let features: TaxifareDatasetFeaturesItem = self.map(item);
         TaxifareDatasetMappedItem {
-            discrete_weekday: item.pickup_weekday,
-            discrete_hour: item.pickup_hour,
-            discrete_am_or_pm: item.am_or_pm,
-            continuous_features: [
-                item.pickup_latitude,
-                item.pickup_longitude,
-                item.dropoff_latitude,
-                item.dropoff_longitude,
-                item.passenger_count,
-                item.distance,
-            ],
+            discrete_weekday: features.discrete_weekday,
+            discrete_hour: features.discrete_hour,
+            discrete_am_or_pm: features.discrete_am_or_pm,
+            continuous_features: features.continuous_features,
+            continuous_mask: features.continuous_mask,
             label: item.fare_amount,
+            weight: self.sample_weights.weight(item),
         }
     }
 }
 
 pub(crate) type TaxifareMappedDataset =
-    MapperDataset<TaxifareRawDataset, RawDatafieldToFeaturesMapper, TaxifareDatasetRawItem>;
+    MapperDataset<TaxifareSourceDataset, RawDatafieldToFeaturesMapper, TaxifareDatasetRawItem>;
@@ -1,43 +1,182 @@
-use burn::data::dataset::{InMemDataset, transform::ShuffledDataset};
-use csv::ReaderBuilder;
-use rand::{SeedableRng, rngs::StdRng};
-use serde::Deserialize;
+use std::{fs::File, path::Path, sync::Arc};
 
-#[derive(Clone, Debug, Deserialize)]
+use burn::data::dataset::{Dataset, InMemDataset, SqliteDataset, SqliteDatasetWriter};
+use csv::{Reader, ReaderBuilder, StringRecord};
+use dataframe_dataset::{
+    DataFrameDataset, DataFrameItem,
+    polars::prelude::{AnyValue, DataFrame, PolarsResult},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TaxifareDatasetRawItem {
-    pub(crate) fare_amount: f64,
-    pub(crate) pickup_latitude: f64,
-    pub(crate) pickup_longitude: f64,
-    pub(crate) dropoff_latitude: f64,
-    pub(crate) dropoff_longitude: f64,
-    pub(crate) passenger_count: f64,
-    pub(crate) distance: f64,
-    pub(crate) pickup_hour: u8,
-    pub(crate) pickup_weekday: u8,
-    pub(crate) am_or_pm: u8,
+    pub fare_amount: f64,
+    pub pickup_latitude: f64,
+    pub pickup_longitude: f64,
+    pub dropoff_latitude: f64,
+    pub dropoff_longitude: f64,
+    /// Missing values are masked instead of dropping the trip.
+    pub passenger_count: Option<f64>,
+    pub distance: Option<f64>,
+    pub pickup_hour: u8,
+    pub pickup_weekday: u8,
+    pub am_or_pm: u8,
+    /// Seconds since the unix epoch, only needed for recency based weights.
+    #[serde(default)]
+    pub pickup_timestamp: Option<i64>,
+    /// Read from the column selected by [SampleWeighting::Column](super::sample_weights::SampleWeighting::Column).
+    #[serde(default)]
+    pub sample_weight: Option<f64>,
+}
+
+/// Name of the table holding the trips in a converted sqlite database.
+const SQLITE_SPLIT: &str = "trips";
+const SAMPLE_WEIGHT_COLUMN: &str = "sample_weight";
+
+impl DataFrameItem for TaxifareDatasetRawItem {
+    const COLUMNS: &'static [&'static str] = &[
+        "fare_amount",
+        "pickup_latitude",
+        "pickup_longitude",
+        "dropoff_latitude",
+        "dropoff_longitude",
+        "passenger_count",
+        "distance",
+        "pickup_hour",
+        "pickup_weekday",
+        "am_or_pm",
+    ];
+    const OPTIONAL_COLUMNS: &'static [&'static str] = &["pickup_timestamp", SAMPLE_WEIGHT_COLUMN];
+
+    fn from_row(row: &[AnyValue]) -> Option<Self> {
+        Some(Self {
+            fare_amount: row[0].extract()?,
+            pickup_latitude: row[1].extract()?,
+            pickup_longitude: row[2].extract()?,
+            dropoff_latitude: row[3].extract()?,
+            dropoff_longitude: row[4].extract()?,
+            passenger_count: row[5].extract(),
+            distance: row[6].extract(),
+            pickup_hour: row[7].extract()?,
+            pickup_weekday: row[8].extract()?,
+            am_or_pm: row[9].extract()?,
+            pickup_timestamp: row[10].extract(),
+            sample_weight: row[11].extract(),
+        })
+    }
 }
 
 type TaxifareInMemDataset = InMemDataset<TaxifareDatasetRawItem>;
-pub type TaxifareRawDataset = ShuffledDataset<TaxifareInMemDataset, TaxifareDatasetRawItem>;
+type TaxifareSqliteDataset = SqliteDataset<TaxifareDatasetRawItem>;
+type TaxifareDataFrameDataset = DataFrameDataset<TaxifareDatasetRawItem>;
+/// Raw items in file order, either held in memory or read from disk on access.
+pub(crate) type TaxifareSourceDataset = Arc<dyn Dataset<TaxifareDatasetRawItem>>;
+
+pub(crate) fn is_sqlite_file(file_name: &str) -> bool {
+    matches!(
+        Path::new(file_name)
+            .extension()
+            .and_then(|extension| extension.to_str()),
+        Some("db" | "sqlite")
+    )
+}
+
+/// Opens a csv file and renames the selected weight column to the field of the raw item.
+fn csv_reader(
+    file_name: &str,
+    weight_column: Option<&str>,
+) -> Result<Reader<File>, std::io::Error> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_path(file_name)?;
+    if let Some(weight_column) = weight_column {
+        if !reader
+            .headers()?
+            .iter()
+            .any(|header| header == weight_column)
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Weight column {weight_column} does not exist in {file_name}"),
+            ));
+        }
+        let headers = reader
+            .headers()?
+            .iter()
+            .map(|header| {
+                if header == weight_column {
+                    SAMPLE_WEIGHT_COLUMN
+                } else {
+                    header
+                }
+            })
+            .collect::<StringRecord>();
+        reader.set_headers(headers);
+    }
+    Ok(reader)
+}
+
+///
+/// One-time conversion of a prepared csv file into a sqlite database.
+/// The csv file is streamed, so it does not have to fit into memory.
+/// Returns the number of converted items.
+///
+pub fn convert_csv_to_sqlite(
+    csv_file: &str,
+    db_file: &str,
+    weight_column: Option<&str>,
+) -> Result<usize, std::io::Error> {
+    let mut reader = csv_reader(csv_file, weight_column)?;
+    let mut writer = SqliteDatasetWriter::<TaxifareDatasetRawItem>::new(db_file, true)
+        .map_err(std::io::Error::other)?;
+    let mut count = 0;
+    for item in reader.deserialize::<TaxifareDatasetRawItem>() {
+        writer
+            .write(SQLITE_SPLIT, &item?)
+            .map_err(std::io::Error::other)?;
+        count += 1;
+    }
+    writer.set_completed().map_err(std::io::Error::other)?;
+    Ok(count)
+}
+
+/// Reads the items from the rows of a data frame, e.g. the output of `create_input_dataset`.
+pub(crate) fn load_dataframe(df: &DataFrame) -> PolarsResult<TaxifareSourceDataset> {
+    Ok(Arc::new(TaxifareDataFrameDataset::new(df)?))
+}
 
 pub(crate) struct TaxifareRawDatasetBuilder<'a> {
     file_name: &'a str,
-    seed: Option<u64>,
+    weight_column: Option<&'a str>,
 }
 
 impl<'a> TaxifareRawDatasetBuilder<'a> {
-    pub fn new(file_name: &'a str, seed: Option<u64>) -> Self {
-        Self { file_name, seed }
+    pub fn new(file_name: &'a str) -> Self {
+        Self {
+            file_name,
+            weight_column: None,
+        }
+    }
+
+    /// Selects the csv column holding the sample weights.
+    /// Sqlite databases already contain the column selected on conversion.
+    pub fn with_weight_column(mut self, weight_column: Option<&'a str>) -> Self {
+        self.weight_column = weight_column;
+        self
     }
 
-    pub fn init(&self) -> Result<TaxifareRawDataset, std::io::Error> {
-        let mut reader_builder = ReaderBuilder::new();
-        reader_builder.has_headers(true);
-        let dataset = TaxifareInMemDataset::from_csv(self.file_name, &reader_builder)?;
-        if let Some(seed) = self.seed {
-            Ok(TaxifareRawDataset::with_seed(dataset, seed))
-        } else {
-            Ok(TaxifareRawDataset::new(dataset, &mut StdRng::from_os_rng()))
+    /// Reads the items in file order.
+    /// Sqlite databases created by [convert_csv_to_sqlite] stay on disk,
+    /// csv files are loaded into memory.
+    pub fn load(&self) -> Result<TaxifareSourceDataset, std::io::Error> {
+        if is_sqlite_file(self.file_name) {
+            let dataset = TaxifareSqliteDataset::from_db_file(self.file_name, SQLITE_SPLIT)
+                .map_err(std::io::Error::other)?;
+            return Ok(Arc::new(dataset));
         }
+        let items = csv_reader(self.file_name, self.weight_column)?
+            .deserialize()
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Arc::new(TaxifareInMemDataset::new(items)))
     }
 }
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use super::raw_dataset::TaxifareDatasetRawItem;
+
+const SECONDS_PER_DAY: f64 = 24.0 * 60.0 * 60.0;
+
+///
+/// How much each item contributes to the loss.
+/// Deserializing fails for a `half_life_days` which is not positive.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedSampleWeighting")]
+pub enum SampleWeighting {
+    /// Every item has the same weight, the loss is a plain mean.
+    #[default]
+    Uniform,
+    /// Weights are read from the named column of the prepared file.
+    Column(String),
+    /// Weights halve every `half_life_days` before the most recent pickup in the data.
+    RecencyDecay { half_life_days: f64 },
+}
+
+/// Serialized form of a [SampleWeighting] before its parameters are validated.
+#[derive(Deserialize)]
+enum UncheckedSampleWeighting {
+    Uniform,
+    Column(String),
+    RecencyDecay { half_life_days: f64 },
+}
+
+impl TryFrom<UncheckedSampleWeighting> for SampleWeighting {
+    type Error = std::io::Error;
+
+    fn try_from(weighting: UncheckedSampleWeighting) -> Result<Self, Self::Error> {
+        match weighting {
+            UncheckedSampleWeighting::Uniform => Ok(SampleWeighting::Uniform),
+            UncheckedSampleWeighting::Column(column) => Ok(SampleWeighting::Column(column)),
+            UncheckedSampleWeighting::RecencyDecay { half_life_days } => {
+                SampleWeighting::RecencyDecay { half_life_days }.validated()
+            }
+        }
+    }
+}
+
+impl SampleWeighting {
+    /// Returns the weighting if its parameters are valid.
+    pub fn validated(self) -> Result<Self, std::io::Error> {
+        match self {
+            SampleWeighting::RecencyDecay { half_life_days }
+                if !(half_life_days > 0.0 && half_life_days.is_finite()) =>
+            {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Recency half life has to be positive, got {half_life_days} days"),
+                ))
+            }
+            weighting => Ok(weighting),
+        }
+    }
+
+    /// The column of the prepared file which holds the weights, if any.
+    pub fn column(&self) -> Option<&str> {
+        match self {
+            SampleWeighting::Column(column) => Some(column),
+            _ => None,
+        }
+    }
+}
+
+///
+/// Weighting resolved against a dataset.
+/// The recency decay is relative to the latest pickup, so that the weights
+/// do not change with the date training is run.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SampleWeights {
+    weighting: SampleWeighting,
+    latest_pickup_timestamp: i64,
+}
+
+impl SampleWeights {
+    pub fn new(weighting: SampleWeighting, latest_pickup_timestamp: i64) -> Self {
+        Self {
+            weighting,
+            latest_pickup_timestamp,
+        }
+    }
+
+    /// Returns `None` for uniform weighting. Items without a value are weighted with 1.
+    pub fn weight(&self, item: &TaxifareDatasetRawItem) -> Option<f64> {
+        match &self.weighting {
+            SampleWeighting::Uniform => None,
+            SampleWeighting::Column(_) => Some(item.sample_weight.unwrap_or(1.0)),
+            SampleWeighting::RecencyDecay { half_life_days } => {
+                Some(item.pickup_timestamp.map_or(1.0, |timestamp| {
+                    let age_days =
+                        (self.latest_pickup_timestamp - timestamp) as f64 / SECONDS_PER_DAY;
+                    0.5f64.powf(age_days / half_life_days)
+                }))
+            }
+        }
+    }
+}
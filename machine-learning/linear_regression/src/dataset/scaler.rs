@@ -0,0 +1,142 @@
+use std::{fs::File, io::BufReader, path::Path};
+
+use burn::data::dataset::Dataset;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    mapped_dataset::{NUM_CONTINUOUS_FEATURES, continuous_features},
+    raw_dataset::TaxifareDatasetRawItem,
+};
+
+const SCALER_FILE_NAME: &str = "scaler.json";
+
+/// Center and scale of every continuous feature.
+type FeatureStatistics = (
+    [f64; NUM_CONTINUOUS_FEATURES],
+    [f64; NUM_CONTINUOUS_FEATURES],
+);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ScalerKind {
+    /// Centers on the mean and scales by the standard deviation.
+    #[default]
+    Standard,
+    /// Centers on the median and scales by the interquartile range.
+    Robust,
+}
+
+///
+/// Per feature affine transformation of the continuous inputs.
+/// It is fitted on the training split only and stored in the artifact directory
+/// so that inference applies exactly the same transformation.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeatureScaler {
+    kind: ScalerKind,
+    center: [f64; NUM_CONTINUOUS_FEATURES],
+    scale: [f64; NUM_CONTINUOUS_FEATURES],
+}
+
+impl Default for FeatureScaler {
+    fn default() -> Self {
+        Self {
+            kind: ScalerKind::default(),
+            center: [0.0; NUM_CONTINUOUS_FEATURES],
+            scale: [1.0; NUM_CONTINUOUS_FEATURES],
+        }
+    }
+}
+
+impl FeatureScaler {
+    pub fn fit<D: Dataset<TaxifareDatasetRawItem>>(kind: ScalerKind, dataset: &D) -> Self {
+        let (center, scale) = match kind {
+            ScalerKind::Standard => Self::fit_standard(dataset),
+            ScalerKind::Robust => Self::fit_robust(dataset),
+        };
+        Self {
+            kind,
+            center,
+            // Constant features would otherwise be divided by zero.
+            scale: scale.map(|s| if s > f64::EPSILON { s } else { 1.0 }),
+        }
+    }
+
+    fn fit_standard<D: Dataset<TaxifareDatasetRawItem>>(dataset: &D) -> FeatureStatistics {
+        // Welford's online algorithm, numerically stable for large datasets.
+        // Missing values are skipped, so every feature has its own count.
+        let mut count = [0.0; NUM_CONTINUOUS_FEATURES];
+        let mut mean = [0.0; NUM_CONTINUOUS_FEATURES];
+        let mut m2 = [0.0; NUM_CONTINUOUS_FEATURES];
+        for item in (0..dataset.len()).filter_map(|idx| dataset.get(idx)) {
+            for (i, value) in continuous_features(&item).into_iter().enumerate() {
+                let Some(value) = value else {
+                    continue;
+                };
+                count[i] += 1.0;
+                let delta = value - mean[i];
+                mean[i] += delta / count[i];
+                m2[i] += delta * (value - mean[i]);
+            }
+        }
+        let std_dev = std::array::from_fn(|i| {
+            if count[i] > 0.0 {
+                (m2[i] / count[i]).sqrt()
+            } else {
+                1.0
+            }
+        });
+        (mean, std_dev)
+    }
+
+    fn fit_robust<D: Dataset<TaxifareDatasetRawItem>>(dataset: &D) -> FeatureStatistics {
+        let mut columns: [Vec<f64>; NUM_CONTINUOUS_FEATURES] = Default::default();
+        for item in (0..dataset.len()).filter_map(|idx| dataset.get(idx)) {
+            for (i, value) in continuous_features(&item).into_iter().enumerate() {
+                columns[i].extend(value);
+            }
+        }
+        let mut median = [0.0; NUM_CONTINUOUS_FEATURES];
+        let mut iqr = [1.0; NUM_CONTINUOUS_FEATURES];
+        for (i, column) in columns.iter_mut().enumerate() {
+            if column.is_empty() {
+                continue;
+            }
+            column.sort_by(f64::total_cmp);
+            median[i] = quantile(column, 0.5);
+            iqr[i] = quantile(column, 0.75) - quantile(column, 0.25);
+        }
+        (median, iqr)
+    }
+
+    pub fn kind(&self) -> ScalerKind {
+        self.kind
+    }
+
+    /// Missing values are imputed with the center, which is 0 after scaling.
+    pub fn transform(
+        &self,
+        features: [Option<f64>; NUM_CONTINUOUS_FEATURES],
+    ) -> [f64; NUM_CONTINUOUS_FEATURES] {
+        std::array::from_fn(|i| {
+            features[i].map_or(0.0, |value| (value - self.center[i]) / self.scale[i])
+        })
+    }
+
+    pub fn save(&self, artifact_dir: &str) -> Result<(), std::io::Error> {
+        let file = File::create(Path::new(artifact_dir).join(SCALER_FILE_NAME))?;
+        serde_json::to_writer_pretty(file, self).map_err(std::io::Error::other)
+    }
+
+    pub fn load(artifact_dir: &str) -> Result<Self, std::io::Error> {
+        let file = File::open(Path::new(artifact_dir).join(SCALER_FILE_NAME))?;
+        serde_json::from_reader(BufReader::new(file)).map_err(std::io::Error::other)
+    }
+}
+
+/// Linear interpolated quantile of an already sorted slice.
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let pos = (sorted.len() - 1) as f64 * q;
+    let lower = pos.floor() as usize;
+    let upper = pos.ceil() as usize;
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (pos - lower as f64)
+}
@@ -0,0 +1,40 @@
+use std::marker::PhantomData;
+
+use burn::data::dataset::Dataset;
+
+/// Dataset exposing the items of an inner dataset at the given indices, in that order.
+/// Indices may repeat, which is used to oversample items.
+pub struct SubsetDataset<D, I> {
+    dataset: D,
+    indices: Vec<usize>,
+    input: PhantomData<I>,
+}
+
+impl<D, I> SubsetDataset<D, I>
+where
+    D: Dataset<I>,
+{
+    pub fn new(dataset: D, indices: Vec<usize>) -> Self {
+        Self {
+            dataset,
+            indices,
+            input: PhantomData,
+        }
+    }
+}
+
+impl<D, I> Dataset<I> for SubsetDataset<D, I>
+where
+    D: Dataset<I>,
+    I: Send + Sync,
+{
+    fn get(&self, index: usize) -> Option<I> {
+        self.indices
+            .get(index)
+            .and_then(|index| self.dataset.get(*index))
+    }
+
+    fn len(&self) -> usize {
+        self.indices.len()
+    }
+}
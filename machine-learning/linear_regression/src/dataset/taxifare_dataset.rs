@@ -1,48 +1,294 @@
-use burn::data::dataset::{Dataset, transform::PartialDataset};
+use std::{path::PathBuf, sync::Arc};
+
+use burn::{config::Config, data::dataset::Dataset};
+use dataframe_dataset::polars::prelude::{DataFrame, PolarsResult};
+use rand::{RngCore, SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
 
 use super::{
+    feature_cache::{CacheKey, FeatureCache},
     mapped_dataset::{
-        RawDatafieldToFeaturesMapper, TaxifareDatasetMappedItem, TaxifareMappedDataset,
+        CategoricalViolation, RawDatafieldToFeaturesMapper, TaxifareDatasetMappedItem,
+        TaxifareMappedDataset,
+    },
+    raw_dataset::{
+        TaxifareDatasetRawItem, TaxifareRawDatasetBuilder, TaxifareSourceDataset, load_dataframe,
     },
-    raw_dataset::TaxifareRawDatasetBuilder,
+    sample_weights::{SampleWeighting, SampleWeights},
+    scaler::{FeatureScaler, ScalerKind},
+    subset_dataset::SubsetDataset,
 };
 
-type TaxifareDataset = PartialDataset<TaxifareMappedDataset, TaxifareDatasetMappedItem>;
+/// Mapped items in source order, either mapped lazily or read from the feature cache.
+pub type TaxifareFeatures = Arc<dyn Dataset<TaxifareDatasetMappedItem>>;
+type TaxifareDataset = SubsetDataset<TaxifareFeatures, TaxifareDatasetMappedItem>;
 
-pub struct TaxifareDatasetBuilder<'a> {
-    file_name: &'a str,
-    seed: Option<u64>,
-    train_test_split_percentage: usize,
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SplitStrategy {
+    /// The items are shuffled and cut into train and test.
+    Random,
+    /// Every fare quantile bucket is cut separately, so rare expensive trips
+    /// are represented in both splits in the same proportion.
+    StratifiedByFare { buckets: usize },
 }
 
-impl<'a> TaxifareDatasetBuilder<'a> {
-    pub fn new(file_name: &'a str, train_test_split_percentage: usize, seed: Option<u64>) -> Self {
-        Self {
-            file_name,
+///
+/// Repeats items of rare fare buckets in the training split.
+/// The test split keeps the natural distribution, so validation metrics stay comparable.
+///
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FareOversampling {
+    /// Upper fare bounds of the buckets, `[10.0, 50.0]` results in three buckets.
+    pub fare_edges: Vec<f64>,
+    /// Every bucket is repeated until it holds at least this share of the largest bucket.
+    pub min_bucket_share: f64,
+}
+
+#[derive(Config)]
+pub struct SplitConfig {
+    #[config(default = 75)]
+    pub train_percentage: usize,
+
+    #[config(default = "SplitStrategy::Random")]
+    pub strategy: SplitStrategy,
+
+    #[config(default = "None")]
+    pub oversampling: Option<FareOversampling>,
+}
+
+/// Groups indices by the bucket their fare falls into, buckets are bounded by the sorted edges.
+fn group_by_fare(indices: &[usize], fares: &[f64], fare_edges: &[f64]) -> Vec<Vec<usize>> {
+    let mut groups = vec![Vec::new(); fare_edges.len() + 1];
+    for (index, fare) in indices.iter().zip(fares) {
+        groups[fare_edges.partition_point(|edge| edge <= fare)].push(*index);
+    }
+    groups
+}
+
+/// Fares which cut the sorted fares into buckets of equal size.
+fn quantile_edges(fares: &[f64], buckets: usize) -> Vec<f64> {
+    let mut sorted = fares.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    (1..buckets)
+        .filter_map(|bucket| sorted.get(sorted.len() * bucket / buckets).copied())
+        .collect()
+}
+
+pub struct TaxifareDatasetBuilder {
+    /// Only set for file based datasets, which are the only ones with a feature cache.
+    file_name: Option<String>,
+    source: TaxifareSourceDataset,
+    seed: u64,
+    split: SplitConfig,
+    train_indices: Vec<usize>,
+    test_indices: Vec<usize>,
+}
+
+impl TaxifareDatasetBuilder {
+    pub fn new(
+        file_name: &str,
+        split: SplitConfig,
+        seed: Option<u64>,
+        weight_column: Option<&str>,
+    ) -> Result<Self, std::io::Error> {
+        let source = TaxifareRawDatasetBuilder::new(file_name)
+            .with_weight_column(weight_column)
+            .load()?;
+        Ok(Self::from_source(
+            source,
+            Some(file_name.to_string()),
+            split,
             seed,
-            train_test_split_percentage,
+        ))
+    }
+
+    /// Builds the datasets in memory from the rows of a data frame, e.g. the output of
+    /// `create_input_dataset`. A `sample_weight` column is used if it exists.
+    pub fn from_dataframe(
+        df: &DataFrame,
+        split: SplitConfig,
+        seed: Option<u64>,
+    ) -> PolarsResult<Self> {
+        Ok(Self::from_source(load_dataframe(df)?, None, split, seed))
+    }
+
+    fn from_source(
+        source: TaxifareSourceDataset,
+        file_name: Option<String>,
+        split: SplitConfig,
+        seed: Option<u64>,
+    ) -> Self {
+        let mut builder = Self {
+            file_name,
+            source,
+            seed: seed.unwrap_or_else(|| StdRng::from_os_rng().next_u64()),
+            split,
+            train_indices: Vec::new(),
+            test_indices: Vec::new(),
+        };
+        builder.split_indices();
+        builder
+    }
+
+    fn fares(&self, indices: &[usize]) -> Vec<f64> {
+        indices
+            .iter()
+            .filter_map(|index| self.source.get(*index))
+            .map(|item| item.fare_amount)
+            .collect()
+    }
+
+    fn split_indices(&mut self) {
+        let indices = (0..self.source.len()).collect::<Vec<_>>();
+        let groups = match self.split.strategy {
+            SplitStrategy::Random => vec![indices],
+            SplitStrategy::StratifiedByFare { buckets } => {
+                let fares = self.fares(&indices);
+                group_by_fare(&indices, &fares, &quantile_edges(&fares, buckets))
+            }
+        };
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        for mut group in groups {
+            group.shuffle(&mut rng);
+            let split_idx = group.len() * self.split.train_percentage / 100;
+            self.train_indices.extend_from_slice(&group[..split_idx]);
+            self.test_indices.extend_from_slice(&group[split_idx..]);
         }
+        self.train_indices.shuffle(&mut rng);
+        self.test_indices.shuffle(&mut rng);
+    }
+
+    /// Training indices with the rare fare buckets repeated.
+    fn oversampled_train_indices(&self, oversampling: &FareOversampling) -> Vec<usize> {
+        let fares = self.fares(&self.train_indices);
+        let groups = group_by_fare(&self.train_indices, &fares, &oversampling.fare_edges);
+        let largest = groups.iter().map(Vec::len).max().unwrap_or_default();
+        let min_len = (largest as f64 * oversampling.min_bucket_share).ceil() as usize;
+
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut indices = groups
+            .into_iter()
+            .filter(|group| !group.is_empty())
+            .flat_map(|group| {
+                let len = group.len().max(min_len);
+                group.into_iter().cycle().take(len)
+            })
+            .collect::<Vec<_>>();
+        indices.shuffle(&mut rng);
+        indices
+    }
+
+    /// Fits the feature scaler on the training split only.
+    pub fn fit_scaler(&self, kind: ScalerKind) -> FeatureScaler {
+        let train_dataset = SubsetDataset::<_, TaxifareDatasetRawItem>::new(
+            self.source.clone(),
+            self.train_indices.clone(),
+        );
+        FeatureScaler::fit(kind, &train_dataset)
     }
 
-    pub fn test(&self) -> TaxifareDataset {
-        self.init("test").unwrap()
+    /// Resolves the weighting against the most recent pickup of the whole dataset.
+    pub fn sample_weights(
+        &self,
+        weighting: SampleWeighting,
+    ) -> Result<SampleWeights, std::io::Error> {
+        let latest_pickup_timestamp = match weighting {
+            SampleWeighting::RecencyDecay { .. } => (0..self.source.len())
+                .filter_map(|idx| self.source.get(idx))
+                .filter_map(|item| item.pickup_timestamp)
+                .max()
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        "Recency weights require a pickup_timestamp column",
+                    )
+                })?,
+            _ => 0,
+        };
+        Ok(SampleWeights::new(weighting, latest_pickup_timestamp))
     }
 
-    pub fn train(&self) -> TaxifareDataset {
-        self.init("train").unwrap()
+    /// Reports categorical values the mapper can not embed, with rows in file order.
+    pub fn categorical_violations(
+        &self,
+        mapper: &RawDatafieldToFeaturesMapper,
+    ) -> Vec<CategoricalViolation> {
+        mapper.categorical_violations(&self.source)
     }
 
-    fn init(&self, split: &str) -> Result<TaxifareDataset, std::io::Error> {
-        let dataset = TaxifareRawDatasetBuilder::new(self.file_name, self.seed)
-            .init()
-            .expect("Can not read csv file.");
-        let dataset = TaxifareMappedDataset::new(dataset, RawDatafieldToFeaturesMapper);
-        let dataset_len = dataset.len();
-        let split_idx = dataset_len * self.train_test_split_percentage / 100;
+    ///
+    /// Maps all items of the source file.
+    /// With `use_cache` the mapped features of a file based dataset are stored in a binary
+    /// file next to the source, which is reused as long as neither the source file nor the
+    /// mapper change. Fails if the source file can not be read to key the cache,
+    /// problems with the cache file itself are returned as warnings next to the features.
+    ///
+    pub fn features(
+        &self,
+        mapper: &RawDatafieldToFeaturesMapper,
+        use_cache: bool,
+    ) -> Result<(TaxifareFeatures, Vec<String>), std::io::Error> {
+        let dataset = TaxifareMappedDataset::new(self.source.clone(), mapper.clone());
+        let Some(file_name) = self.file_name.as_ref().filter(|_| use_cache) else {
+            return Ok((Arc::new(dataset), Vec::new()));
+        };
+        let key = CacheKey::default().update_file(file_name).map_err(|err| {
+            std::io::Error::new(err.kind(), format!("Can not read {file_name}: {err}"))
+        })?;
+        let cache_file = PathBuf::from(format!("{file_name}.features.bin"));
+        let (cache, warnings) =
+            FeatureCache::load_or_create(&cache_file, mapper.cache_key(key), &dataset);
+        Ok((Arc::new(cache), warnings))
+    }
+
+    /// Raw items of the test or the training split, the training split is not oversampled.
+    pub(crate) fn raw_split(
+        &self,
+        split: &str,
+    ) -> Result<SubsetDataset<TaxifareSourceDataset, TaxifareDatasetRawItem>, std::io::Error> {
+        let indices = match split {
+            "train" => self.train_indices.clone(),
+            "test" => self.test_indices.clone(),
+            _ => return Err(unknown_split(split)),
+        };
+        Ok(SubsetDataset::new(self.source.clone(), indices))
+    }
+
+    pub fn test(&self, features: &TaxifareFeatures) -> TaxifareDataset {
+        self.init("test", features).unwrap()
+    }
+
+    /// The training split, oversampled if configured.
+    pub fn train(&self, features: &TaxifareFeatures) -> TaxifareDataset {
+        self.init("train", features).unwrap()
+    }
+
+    fn init(
+        &self,
+        split: &str,
+        features: &TaxifareFeatures,
+    ) -> Result<TaxifareDataset, std::io::Error> {
         match split {
-            "train" => Ok(TaxifareDataset::new(dataset, 0, split_idx)),
-            "test" => Ok(TaxifareDataset::new(dataset, split_idx, dataset_len)),
-            _ => panic!("Unknown split: {split}"),
+            "train" => {
+                let indices = match &self.split.oversampling {
+                    Some(oversampling) => self.oversampled_train_indices(oversampling),
+                    None => self.train_indices.clone(),
+                };
+                Ok(TaxifareDataset::new(features.clone(), indices))
+            }
+            "test" => Ok(TaxifareDataset::new(
+                features.clone(),
+                self.test_indices.clone(),
+            )),
+            _ => Err(unknown_split(split)),
         }
     }
 }
+
+fn unknown_split(split: &str) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Unknown split {split}, expected train or test"),
+    )
+}
@@ -0,0 +1,271 @@
+use std::{
+    fs::File,
+    io::{BufReader, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use burn::train::{
+    EarlyStoppingStrategy,
+    checkpoint::{CheckpointingAction, CheckpointingStrategy},
+    metric::store::{Aggregate, EventStoreClient, Split},
+};
+use serde::{Deserialize, Serialize};
+
+const REPORT_FILE_NAME: &str = "early_stopping.json";
+/// State of a running training, needed to continue early stopping on resume.
+const STATE_FILE_NAME: &str = "early_stopping_state.json";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum MetricDirection {
+    /// Lower values are better, e.g. the loss.
+    #[default]
+    Lowest,
+    /// Higher values are better, e.g. R².
+    Highest,
+}
+
+/// Stops training once the validation metric has not improved for `patience` epochs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EarlyStoppingConfig {
+    /// Name of the validation metric as shown in the dashboard, e.g. `Loss`, `RMSE` or `R2`.
+    pub metric: String,
+    pub direction: MetricDirection,
+    pub patience: usize,
+    /// Smallest change of the metric which counts as an improvement.
+    pub min_delta: f64,
+}
+
+/// Written to the artifact directory after training with early stopping.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EarlyStoppingReport {
+    pub metric: String,
+    /// Epoch of the restored model.
+    pub best_epoch: usize,
+    pub best_value: f64,
+    pub last_epoch: usize,
+    pub reason: String,
+}
+
+impl EarlyStoppingReport {
+    pub fn save(&self, artifact_dir: &str) -> Result<(), std::io::Error> {
+        let file = File::create(Path::new(artifact_dir).join(REPORT_FILE_NAME))?;
+        serde_json::to_writer_pretty(file, self).map_err(std::io::Error::other)
+    }
+
+    pub fn load(artifact_dir: &str) -> Result<Self, std::io::Error> {
+        let file = File::open(Path::new(artifact_dir).join(REPORT_FILE_NAME))?;
+        serde_json::from_reader(BufReader::new(file)).map_err(std::io::Error::other)
+    }
+}
+
+///
+/// Source of the validation metrics of finished epochs.
+/// During training this is the event store of the learner, which holds the mean over the
+/// entries of the epoch, see [crate::metrics::RegressionMetric].
+///
+pub trait ValidationMetrics {
+    fn valid_metric(&self, name: &str, epoch: usize) -> Option<f64>;
+}
+
+impl ValidationMetrics for EventStoreClient {
+    fn valid_metric(&self, name: &str, epoch: usize) -> Option<f64> {
+        self.find_metric(name, epoch, Aggregate::Mean, Split::Valid)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct EarlyStoppingState {
+    /// Recorded metric value of every epoch, the rest of the state follows from it.
+    values: Vec<(usize, f64)>,
+    /// Epoch and value of the last improvement.
+    best: Option<(usize, f64)>,
+    /// Best epoch before the last improvement, its checkpoint is no longer needed.
+    replaced_best: Option<usize>,
+    last_epoch: usize,
+    stopped: bool,
+    /// Why the state of the last epoch could not be saved, training continues regardless.
+    #[serde(skip)]
+    save_error: Option<String>,
+}
+
+///
+/// Early stopping on a validation metric.
+/// The strategy is shared with the learner, which runs it as early stopping strategy and,
+/// through [EarlyStopping::checkpointing], keeps the checkpoint of the best epoch.
+///
+#[derive(Clone)]
+pub struct EarlyStopping {
+    config: EarlyStoppingConfig,
+    state: Arc<Mutex<EarlyStoppingState>>,
+    state_file: Option<PathBuf>,
+}
+
+impl EarlyStopping {
+    pub fn new(config: EarlyStoppingConfig) -> Self {
+        Self {
+            config,
+            state: Default::default(),
+            state_file: None,
+        }
+    }
+
+    ///
+    /// Saves the state to the artifact directory after every epoch.
+    /// When resuming from the checkpoint of an epoch, the state saved by the previous run is
+    /// restored up to that epoch, so the best epoch and the patience carry over.
+    ///
+    pub fn persisted(
+        mut self,
+        artifact_dir: &str,
+        checkpoint: Option<usize>,
+    ) -> Result<Self, std::io::Error> {
+        let state_file = Path::new(artifact_dir).join(STATE_FILE_NAME);
+        if let Some(checkpoint) = checkpoint {
+            let saved: EarlyStoppingState = match File::open(&state_file) {
+                Ok(file) => {
+                    serde_json::from_reader(BufReader::new(file)).map_err(std::io::Error::other)?
+                }
+                Err(err) if err.kind() == ErrorKind::NotFound => Default::default(),
+                Err(err) => return Err(err),
+            };
+            // Epochs after the checkpoint are trained again
+            for (epoch, value) in saved.values {
+                if epoch <= checkpoint {
+                    self.record(epoch, Some(value));
+                }
+            }
+            self.record(checkpoint, None);
+        }
+        self.state_file = Some(state_file);
+        Ok(self)
+    }
+
+    /// Keeps the checkpoint of the best epoch, the other ones may be deleted.
+    pub fn checkpointing(&self) -> BestEpochCheckpointing {
+        BestEpochCheckpointing {
+            early_stopping: self.clone(),
+        }
+    }
+
+    fn is_improvement(&self, value: f64, best: f64) -> bool {
+        match self.config.direction {
+            MetricDirection::Lowest => value < best - self.config.min_delta,
+            MetricDirection::Highest => value > best + self.config.min_delta,
+        }
+    }
+
+    /// Reads the validation metric of the epoch, every epoch is only recorded once.
+    fn observe(&self, epoch: usize, metrics: &impl ValidationMetrics) {
+        if self.state.lock().unwrap().last_epoch >= epoch {
+            return;
+        }
+        self.record(epoch, metrics.valid_metric(&self.config.metric, epoch));
+        if let Some(state_file) = &self.state_file {
+            let mut state = self.state.lock().unwrap();
+            let save_error = File::create(state_file)
+                .and_then(|file| {
+                    serde_json::to_writer(file, &*state).map_err(std::io::Error::other)
+                })
+                .err()
+                .map(|err| {
+                    format!(
+                        "Early stopping state of epoch {epoch} can not be saved to {}: {err}",
+                        state_file.display()
+                    )
+                });
+            state.save_error = save_error;
+        }
+    }
+
+    ///
+    /// Why the state of the last epoch could not be saved, `None` if it was saved.
+    /// A run resumed from a later checkpoint then starts early stopping from scratch.
+    ///
+    pub fn save_error(&self) -> Option<String> {
+        self.state.lock().unwrap().save_error.clone()
+    }
+
+    fn record(&self, epoch: usize, value: Option<f64>) {
+        let mut state = self.state.lock().unwrap();
+        if state.last_epoch >= epoch {
+            return;
+        }
+        state.last_epoch = epoch;
+        let Some(value) = value else {
+            return;
+        };
+        state.values.push((epoch, value));
+        match state.best {
+            Some((_, best)) if !self.is_improvement(value, best) => {}
+            previous => {
+                state.replaced_best = previous.map(|(epoch, _)| epoch);
+                state.best = Some((epoch, value));
+            }
+        }
+    }
+
+    /// Records the metric of the finished epoch and decides whether to stop training.
+    pub fn should_stop_after(&self, epoch: usize, metrics: &impl ValidationMetrics) -> bool {
+        self.observe(epoch, metrics);
+        let mut state = self.state.lock().unwrap();
+        let Some((best_epoch, _)) = state.best else {
+            return false;
+        };
+        state.stopped = epoch - best_epoch >= self.config.patience;
+        state.stopped
+    }
+
+    /// Summary of the finished training, `None` if the metric was never recorded.
+    pub fn report(&self) -> Option<EarlyStoppingReport> {
+        let state = self.state.lock().unwrap();
+        let (best_epoch, best_value) = state.best?;
+        let reason = if state.stopped {
+            format!(
+                "No improvement of {} by more than {} for {} epochs",
+                self.config.metric, self.config.min_delta, self.config.patience
+            )
+        } else {
+            "Completed all epochs".to_string()
+        };
+        Some(EarlyStoppingReport {
+            metric: self.config.metric.clone(),
+            best_epoch,
+            best_value,
+            last_epoch: state.last_epoch,
+            reason,
+        })
+    }
+}
+
+impl EarlyStoppingStrategy for EarlyStopping {
+    fn should_stop(&mut self, epoch: usize, store: &EventStoreClient) -> bool {
+        self.should_stop_after(epoch, store)
+    }
+}
+
+/// Checkpointing strategy of an [EarlyStopping], see [EarlyStopping::checkpointing].
+#[derive(Clone)]
+pub struct BestEpochCheckpointing {
+    early_stopping: EarlyStopping,
+}
+
+impl CheckpointingStrategy for BestEpochCheckpointing {
+    fn checkpointing(
+        &mut self,
+        epoch: usize,
+        store: &EventStoreClient,
+    ) -> Vec<CheckpointingAction> {
+        self.early_stopping.observe(epoch, store);
+        let state = self.early_stopping.state.lock().unwrap();
+        match state.best {
+            Some((best_epoch, _)) if best_epoch == epoch => {
+                let mut actions = vec![CheckpointingAction::Save];
+                actions.extend(state.replaced_best.map(CheckpointingAction::Delete));
+                actions
+            }
+            // Not saving the epoch allows it to be deleted by the other strategies
+            _ => Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,207 @@
+use std::{collections::BTreeMap, fmt::Display, fs::File, path::Path, str::FromStr};
+
+use burn::{data::dataset::Dataset, prelude::Backend};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dataset::{
+        raw_dataset::{TaxifareDatasetRawItem, TaxifareRawDatasetBuilder},
+        taxifare_dataset::TaxifareDatasetBuilder,
+    },
+    inference::{load_model, predict},
+    metrics::{ErrorStatistics, RegressionScores},
+    training::SPLIT_SEED,
+};
+
+/// Columns the metrics are broken down by, in the order of the report.
+const GROUP_COLUMNS: [&str; 5] = [
+    "pickup_hour",
+    "pickup_weekday",
+    "am_or_pm",
+    "distance_bucket",
+    "fare_bucket",
+];
+/// Upper bounds of the distance buckets in km.
+const DISTANCE_EDGES: [f64; 5] = [1.0, 2.0, 5.0, 10.0, 20.0];
+/// Upper bounds of the fare buckets in dollars.
+const FARE_EDGES: [f64; 4] = [5.0, 10.0, 20.0, 50.0];
+
+/// Items of the prepared file which are evaluated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum EvaluationSplit {
+    #[default]
+    All,
+    /// The training split as used for training, without oversampling.
+    Train,
+    Test,
+}
+
+impl FromStr for EvaluationSplit {
+    type Err = std::io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "all" => Ok(EvaluationSplit::All),
+            "train" => Ok(EvaluationSplit::Train),
+            "test" => Ok(EvaluationSplit::Test),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Unknown split {name}, expected all, train or test"),
+            )),
+        }
+    }
+}
+
+impl Display for EvaluationSplit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            EvaluationSplit::All => "all",
+            EvaluationSplit::Train => "train",
+            EvaluationSplit::Test => "test",
+        })
+    }
+}
+
+/// Metrics of the items of one group, e.g. all trips with a `pickup_hour` of 13.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GroupScores {
+    /// Column the items are grouped by, `overall` for all items.
+    pub group: String,
+    pub value: String,
+    pub count: usize,
+    pub rmse: f64,
+    pub mae: f64,
+    pub r2: f64,
+    pub mape: f64,
+    pub median_ae: f64,
+}
+
+impl GroupScores {
+    fn new(group: &str, value: String, scores: RegressionScores) -> Self {
+        Self {
+            group: group.to_string(),
+            value,
+            count: scores.count,
+            rmse: scores.rmse,
+            mae: scores.mae,
+            r2: scores.r2,
+            mape: scores.mape,
+            median_ae: scores.median_ae,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EvaluationReport {
+    pub data_file: String,
+    pub split: EvaluationSplit,
+    pub overall: RegressionScores,
+    pub groups: Vec<GroupScores>,
+}
+
+impl EvaluationReport {
+    /// Writes `evaluation_<split>.json` and `evaluation_<split>.csv`,
+    /// the first row of the csv file holds the overall metrics.
+    pub fn save(&self, output_dir: &str) -> Result<(), std::io::Error> {
+        let output_dir = Path::new(output_dir);
+        let file = File::create(output_dir.join(format!("evaluation_{}.json", self.split)))?;
+        serde_json::to_writer_pretty(file, self).map_err(std::io::Error::other)?;
+
+        let mut writer =
+            csv::Writer::from_path(output_dir.join(format!("evaluation_{}.csv", self.split)))?;
+        writer.serialize(GroupScores::new(
+            "overall",
+            "all".to_string(),
+            self.overall.clone(),
+        ))?;
+        for group in &self.groups {
+            writer.serialize(group)?;
+        }
+        writer.flush()
+    }
+}
+
+/// Index and label of the bucket bounded by the sorted upper edges.
+fn bucket(edges: &[f64], value: f64) -> (usize, String) {
+    let index = edges.partition_point(|edge| *edge <= value);
+    let label = match (
+        index.checked_sub(1).map(|lower| edges[lower]),
+        edges.get(index),
+    ) {
+        (Some(lower), Some(upper)) => format!("{lower}-{upper}"),
+        (None, Some(upper)) => format!("<{upper}"),
+        (Some(lower), None) => format!(">={lower}"),
+        (None, None) => "all".to_string(),
+    };
+    (index, label)
+}
+
+/// Index and label of the group of the item for every column of [GROUP_COLUMNS].
+fn groups(item: &TaxifareDatasetRawItem) -> [(usize, String); GROUP_COLUMNS.len()] {
+    [
+        (item.pickup_hour as usize, item.pickup_hour.to_string()),
+        (
+            item.pickup_weekday as usize,
+            item.pickup_weekday.to_string(),
+        ),
+        (item.am_or_pm as usize, item.am_or_pm.to_string()),
+        match item.distance {
+            Some(distance) => bucket(&DISTANCE_EDGES, distance),
+            None => (DISTANCE_EDGES.len() + 1, "missing".to_string()),
+        },
+        bucket(&FARE_EDGES, item.fare_amount),
+    ]
+}
+
+///
+/// Evaluates the model of an artifact directory on a prepared csv file or sqlite database.
+/// The train and test splits are reproduced with the split config of the training run.
+/// Fails if the model or the data file can not be loaded.
+///
+pub fn evaluate<B: Backend>(
+    artifact_dir: &str,
+    data_file: &str,
+    split: EvaluationSplit,
+    device: B::Device,
+) -> Result<EvaluationReport, std::io::Error> {
+    let (config, model, mapper) = load_model::<B>(artifact_dir, &device)?;
+    let dataset: Box<dyn Dataset<TaxifareDatasetRawItem>> = match split {
+        EvaluationSplit::All => Box::new(TaxifareRawDatasetBuilder::new(data_file).load()?),
+        EvaluationSplit::Train | EvaluationSplit::Test => Box::new(
+            TaxifareDatasetBuilder::new(data_file, config.split.clone(), Some(SPLIT_SEED), None)?
+                .raw_split(&split.to_string())?,
+        ),
+    };
+    let items = dataset.iter().collect::<Vec<_>>();
+    let predictions = predict(
+        &model,
+        &mapper,
+        items.iter().cloned(),
+        config.batch_size,
+        &device,
+    );
+
+    let mut overall = ErrorStatistics::new(true);
+    let mut grouped = BTreeMap::<(usize, usize, String), ErrorStatistics>::new();
+    for (item, prediction) in items.iter().zip(predictions) {
+        overall.add(prediction as f64, item.fare_amount);
+        for (column, (index, label)) in groups(item).into_iter().enumerate() {
+            grouped
+                .entry((column, index, label))
+                .or_insert_with(|| ErrorStatistics::new(true))
+                .add(prediction as f64, item.fare_amount);
+        }
+    }
+
+    Ok(EvaluationReport {
+        data_file: data_file.to_string(),
+        split,
+        overall: overall.scores(),
+        groups: grouped
+            .into_iter()
+            .map(|((column, _, label), mut statistics)| {
+                GroupScores::new(GROUP_COLUMNS[column], label, statistics.scores())
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,83 @@
+use std::{io::ErrorKind, path::Path};
+
+use burn::{
+    config::Config,
+    data::{
+        dataloader::DataLoaderBuilder,
+        dataset::{InMemDataset, transform::Mapper},
+    },
+    module::Module,
+    prelude::Backend,
+    record::{CompactRecorder, Recorder},
+};
+
+use crate::{
+    batcher::TaxifareInferenceBatcher,
+    dataset::{
+        mapped_dataset::{RawDatafieldToFeaturesMapper, TaxifareDatasetFeaturesItem},
+        raw_dataset::TaxifareDatasetRawItem,
+        scaler::FeatureScaler,
+    },
+    models::taxifare_model::Model,
+    training::TrainingConfig,
+};
+
+///
+/// Loads the config, the trained model and the fitted feature mapping of an artifact directory.
+/// A missing artifact directory is reported as [ErrorKind::NotFound].
+///
+pub(crate) fn load_model<B: Backend>(
+    artifact_dir: &str,
+    device: &B::Device,
+) -> Result<(TrainingConfig, Model<B>, RawDatafieldToFeaturesMapper), std::io::Error> {
+    if !Path::new(artifact_dir).is_dir() {
+        return Err(std::io::Error::new(
+            ErrorKind::NotFound,
+            format!("Artifact directory {artifact_dir} does not exist"),
+        ));
+    }
+    let config = TrainingConfig::load(format!("{artifact_dir}/config.json")).map_err(|err| {
+        std::io::Error::other(format!("Config of {artifact_dir} can not be loaded: {err}"))
+    })?;
+    let record = CompactRecorder::new()
+        .load(format!("{artifact_dir}/model").into(), device)
+        .map_err(|err| {
+            std::io::Error::other(format!("Model of {artifact_dir} can not be loaded: {err}"))
+        })?;
+    let scaler = FeatureScaler::load(artifact_dir)?;
+
+    let model = config.model.init::<B>(device).load_record(record);
+    let mapper =
+        RawDatafieldToFeaturesMapper::new(scaler, &config.model.categorical_cardinalities())?;
+    Ok((config, model, mapper))
+}
+
+/// Predicted fares of the items in the given order.
+pub(crate) fn predict<B: Backend>(
+    model: &Model<B>,
+    mapper: &RawDatafieldToFeaturesMapper,
+    items: impl Iterator<Item = TaxifareDatasetRawItem>,
+    batch_size: usize,
+    device: &B::Device,
+) -> Vec<f32> {
+    let items = items
+        .map(|item| mapper.map(&item))
+        .collect::<Vec<TaxifareDatasetFeaturesItem>>();
+    let dataloader = DataLoaderBuilder::new(TaxifareInferenceBatcher)
+        .batch_size(batch_size.max(1))
+        .set_device(device.clone())
+        .build(InMemDataset::new(items));
+
+    model.predict_batch(dataloader)
+}
+
+pub fn infer<B: Backend>(
+    artifact_dir: &str,
+    device: B::Device,
+    items: Vec<TaxifareDatasetRawItem>,
+) -> Vec<f32> {
+    let (_, model, mapper) =
+        load_model::<B>(artifact_dir, &device).expect("Model can not be loaded.");
+    let batch_size = items.len();
+    predict(&model, &mapper, items.into_iter(), batch_size, &device)
+}
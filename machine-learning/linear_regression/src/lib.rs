@@ -1,4 +1,15 @@
+pub mod backend;
+pub mod batch_prediction;
 pub mod batcher;
 pub mod dataset;
+pub mod early_stopping;
+pub mod evaluation;
+pub mod inference;
+pub mod loss;
+pub mod lr_schedule;
+pub mod metrics;
 pub mod models;
+pub mod optimizer;
+pub mod predictor;
+pub mod server;
 pub mod training;
@@ -0,0 +1,129 @@
+use std::{f64::consts::LN_2, fmt::Display};
+
+use burn::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Keeps the gradient of the RMSLE finite at a loss of 0.
+const RMSLE_EPSILON: f64 = 1e-8;
+/// Lower bound of the sum of the sample weights, a batch with only zero weights has a loss of 0.
+const MIN_WEIGHT_SUM: f64 = 1e-8;
+
+///
+/// Loss minimized by training and reported as the loss of every step.
+/// Deserializing fails for invalid parameters, e.g. a Huber delta which is not positive.
+///
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedLossFunction")]
+pub enum LossFunction {
+    /// Mean squared error.
+    #[default]
+    Mse,
+    /// Mean absolute error, robust against outlier fares.
+    Mae,
+    /// Squared error for errors up to `delta` and absolute error beyond.
+    Huber { delta: f64 },
+    /// Logarithm of the hyperbolic cosine, smooth like MSE near 0 and like MAE for large errors.
+    LogCosh,
+    /// Root mean squared logarithmic error, negative predictions are clamped to 0.
+    Rmsle,
+}
+
+/// Serialized form of a [LossFunction] before its parameters are validated.
+#[derive(Deserialize)]
+enum UncheckedLossFunction {
+    Mse,
+    Mae,
+    Huber { delta: f64 },
+    LogCosh,
+    Rmsle,
+}
+
+impl TryFrom<UncheckedLossFunction> for LossFunction {
+    type Error = std::io::Error;
+
+    fn try_from(loss: UncheckedLossFunction) -> Result<Self, Self::Error> {
+        match loss {
+            UncheckedLossFunction::Mse => Ok(LossFunction::Mse),
+            UncheckedLossFunction::Mae => Ok(LossFunction::Mae),
+            UncheckedLossFunction::Huber { delta } => LossFunction::Huber { delta }.validated(),
+            UncheckedLossFunction::LogCosh => Ok(LossFunction::LogCosh),
+            UncheckedLossFunction::Rmsle => Ok(LossFunction::Rmsle),
+        }
+    }
+}
+
+impl Display for LossFunction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LossFunction::Mse => write!(f, "MSE"),
+            LossFunction::Mae => write!(f, "MAE"),
+            LossFunction::Huber { delta } => write!(f, "Huber (delta {delta})"),
+            LossFunction::LogCosh => write!(f, "log-cosh"),
+            LossFunction::Rmsle => write!(f, "RMSLE"),
+        }
+    }
+}
+
+impl LossFunction {
+    /// Returns the loss if its parameters are valid.
+    pub fn validated(self) -> Result<Self, std::io::Error> {
+        match self {
+            LossFunction::Huber { delta } if !(delta > 0.0 && delta.is_finite()) => {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("Huber delta has to be positive, got {delta}"),
+                ))
+            }
+            loss => Ok(loss),
+        }
+    }
+
+    /// Loss of every prediction before the reduction.
+    fn errors<B: Backend>(&self, output: Tensor<B, 2>, targets: Tensor<B, 2>) -> Tensor<B, 2> {
+        match *self {
+            LossFunction::Mse => (output - targets).powf_scalar(2.0),
+            LossFunction::Mae => (output - targets).abs(),
+            LossFunction::Huber { delta } => {
+                // With q = min(|e|, delta) this is 0.5 * e^2 within delta
+                // and delta * (|e| - 0.5 * delta) beyond it.
+                let absolute = (output - targets).abs();
+                let quadratic = absolute.clone().clamp_max(delta);
+                quadratic.clone().powf_scalar(2.0).mul_scalar(0.5)
+                    + (absolute - quadratic).mul_scalar(delta)
+            }
+            LossFunction::LogCosh => {
+                // log(cosh(e)) = |e| + log(1 + exp(-2|e|)) - log(2) does not overflow
+                let absolute = (output - targets).abs();
+                absolute.clone() + absolute.mul_scalar(-2.0).exp().log1p() - LN_2
+            }
+            LossFunction::Rmsle => {
+                (output.clamp_min(0.0).log1p() - targets.clamp_min(0.0).log1p()).powf_scalar(2.0)
+            }
+        }
+    }
+
+    ///
+    /// Mean loss of the batch, weighted by the sample weights if there are any.
+    /// # Shapes
+    ///   - Output, targets and weights [batch_size, 1]
+    ///   - Loss [1]
+    ///
+    pub fn forward<B: Backend>(
+        &self,
+        output: Tensor<B, 2>,
+        targets: Tensor<B, 2>,
+        weights: Option<Tensor<B, 2>>,
+    ) -> Tensor<B, 1> {
+        let errors = self.errors(output, targets);
+        let loss = match weights {
+            Some(weights) => {
+                (errors * weights.clone()).sum() / weights.sum().clamp_min(MIN_WEIGHT_SUM)
+            }
+            None => errors.mean(),
+        };
+        match self {
+            LossFunction::Rmsle => loss.add_scalar(RMSLE_EPSILON).sqrt(),
+            _ => loss,
+        }
+    }
+}
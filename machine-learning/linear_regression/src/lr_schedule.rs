@@ -0,0 +1,317 @@
+use std::f64::consts::PI;
+
+use burn::{LearningRate, lr_scheduler::LrScheduler, prelude::Backend};
+use serde::{Deserialize, Serialize};
+
+///
+/// How the learning rate changes over the optimizer iterations.
+/// Deserializing fails for invalid parameters, e.g. a period of 0 or a `gamma` which is not positive.
+///
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedLrSchedule")]
+pub enum LrSchedule {
+    #[default]
+    Constant,
+    /// Multiplies the learning rate by `gamma` every `step_size` iterations.
+    StepDecay { step_size: usize, gamma: f64 },
+    /// Multiplies the learning rate by `gamma` every iteration.
+    Exponential { gamma: f64 },
+    /// Anneals the learning rate with a cosine down to `min_lr` and restarts after `period`
+    /// iterations. Every period is `period_mult` times longer than the previous one.
+    CosineWarmRestarts {
+        period: usize,
+        period_mult: usize,
+        min_lr: f64,
+    },
+    /// Increases the learning rate linearly from `start_factor` times its value
+    /// over `warmup_iters` iterations and keeps it constant afterwards.
+    LinearWarmup {
+        warmup_iters: usize,
+        start_factor: f64,
+    },
+    ///
+    /// Raises the learning rate from `learning_rate / div_factor` to the learning rate
+    /// during the first `pct_start` of `total_iters`, then anneals it down to
+    /// `learning_rate / (div_factor * final_div_factor)`. Both phases follow a cosine.
+    ///
+    OneCycle {
+        total_iters: usize,
+        pct_start: f64,
+        div_factor: f64,
+        final_div_factor: f64,
+    },
+}
+
+/// Serialized form of an [LrSchedule] before its parameters are validated.
+#[derive(Deserialize)]
+enum UncheckedLrSchedule {
+    Constant,
+    StepDecay {
+        step_size: usize,
+        gamma: f64,
+    },
+    Exponential {
+        gamma: f64,
+    },
+    CosineWarmRestarts {
+        period: usize,
+        period_mult: usize,
+        min_lr: f64,
+    },
+    LinearWarmup {
+        warmup_iters: usize,
+        start_factor: f64,
+    },
+    OneCycle {
+        total_iters: usize,
+        pct_start: f64,
+        div_factor: f64,
+        final_div_factor: f64,
+    },
+}
+
+impl TryFrom<UncheckedLrSchedule> for LrSchedule {
+    type Error = std::io::Error;
+
+    fn try_from(schedule: UncheckedLrSchedule) -> Result<Self, Self::Error> {
+        match schedule {
+            UncheckedLrSchedule::Constant => LrSchedule::Constant,
+            UncheckedLrSchedule::StepDecay { step_size, gamma } => {
+                LrSchedule::StepDecay { step_size, gamma }
+            }
+            UncheckedLrSchedule::Exponential { gamma } => LrSchedule::Exponential { gamma },
+            UncheckedLrSchedule::CosineWarmRestarts {
+                period,
+                period_mult,
+                min_lr,
+            } => LrSchedule::CosineWarmRestarts {
+                period,
+                period_mult,
+                min_lr,
+            },
+            UncheckedLrSchedule::LinearWarmup {
+                warmup_iters,
+                start_factor,
+            } => LrSchedule::LinearWarmup {
+                warmup_iters,
+                start_factor,
+            },
+            UncheckedLrSchedule::OneCycle {
+                total_iters,
+                pct_start,
+                div_factor,
+                final_div_factor,
+            } => LrSchedule::OneCycle {
+                total_iters,
+                pct_start,
+                div_factor,
+                final_div_factor,
+            },
+        }
+        .validated()
+    }
+}
+
+fn invalid(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidInput, message)
+}
+
+fn check_positive_count(name: &str, value: usize) -> Result<(), std::io::Error> {
+    if value == 0 {
+        return Err(invalid(format!("{name} has to be at least 1")));
+    }
+    Ok(())
+}
+
+fn check_positive(name: &str, value: f64) -> Result<(), std::io::Error> {
+    if !(value > 0.0 && value.is_finite()) {
+        return Err(invalid(format!("{name} has to be positive, got {value}")));
+    }
+    Ok(())
+}
+
+/// Cosine interpolation from `from` at progress 0 to `to` at progress 1.
+fn cosine(from: f64, to: f64, progress: f64) -> f64 {
+    to + (from - to) * (1.0 + (PI * progress.clamp(0.0, 1.0)).cos()) / 2.0
+}
+
+///
+/// Progress of the iteration through its period of a cosine schedule with warm restarts.
+/// The `k`-th period starts at `period * (period_mult^k - 1) / (period_mult - 1)`,
+/// the index of the current one is estimated from the logarithm and corrected exactly.
+///
+fn restart_progress(iteration: usize, period: usize, period_mult: usize) -> f64 {
+    if period_mult == 1 {
+        return (iteration % period) as f64 / period as f64;
+    }
+    let (iteration, period, period_mult) = (iteration as u128, period as u128, period_mult as u128);
+    // Saturates for periods too long to ever be reached
+    let length = |k: u32| {
+        period_mult
+            .checked_pow(k)
+            .and_then(|factor| factor.checked_mul(period))
+            .unwrap_or(u128::MAX)
+    };
+    let start = |k: u32| {
+        period_mult
+            .checked_pow(k)
+            .and_then(|factor| (factor - 1).checked_mul(period))
+            .map_or(u128::MAX, |sum| sum / (period_mult - 1))
+    };
+    let estimate =
+        ((iteration * (period_mult - 1)) as f64 / period as f64 + 1.0).log(period_mult as f64);
+    let mut k = estimate.max(0.0) as u32;
+    while k > 0 && start(k) > iteration {
+        k -= 1;
+    }
+    while start(k + 1) <= iteration {
+        k += 1;
+    }
+    (iteration - start(k)) as f64 / length(k) as f64
+}
+
+impl LrSchedule {
+    /// Returns the schedule if its parameters are valid.
+    pub fn validated(self) -> Result<Self, std::io::Error> {
+        match self {
+            LrSchedule::Constant => {}
+            LrSchedule::StepDecay { step_size, gamma } => {
+                check_positive_count("StepDecay step_size", step_size)?;
+                check_positive("StepDecay gamma", gamma)?;
+            }
+            LrSchedule::Exponential { gamma } => check_positive("Exponential gamma", gamma)?,
+            LrSchedule::CosineWarmRestarts {
+                period,
+                period_mult,
+                min_lr,
+            } => {
+                check_positive_count("CosineWarmRestarts period", period)?;
+                check_positive_count("CosineWarmRestarts period_mult", period_mult)?;
+                if !(min_lr >= 0.0 && min_lr.is_finite()) {
+                    return Err(invalid(format!(
+                        "CosineWarmRestarts min_lr can not be negative, got {min_lr}"
+                    )));
+                }
+            }
+            LrSchedule::LinearWarmup {
+                warmup_iters,
+                start_factor,
+            } => {
+                check_positive_count("LinearWarmup warmup_iters", warmup_iters)?;
+                if !(0.0..=1.0).contains(&start_factor) {
+                    return Err(invalid(format!(
+                        "LinearWarmup start_factor has to be between 0 and 1, got {start_factor}"
+                    )));
+                }
+            }
+            LrSchedule::OneCycle {
+                total_iters,
+                pct_start,
+                div_factor,
+                final_div_factor,
+            } => {
+                check_positive_count("OneCycle total_iters", total_iters)?;
+                if !(pct_start > 0.0 && pct_start < 1.0) {
+                    return Err(invalid(format!(
+                        "OneCycle pct_start has to be between 0 and 1, got {pct_start}"
+                    )));
+                }
+                check_positive("OneCycle div_factor", div_factor)?;
+                check_positive("OneCycle final_div_factor", final_div_factor)?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// Learning rate of the iteration, counted from 0.
+    pub fn learning_rate(&self, learning_rate: f64, iteration: usize) -> f64 {
+        match *self {
+            LrSchedule::Constant => learning_rate,
+            LrSchedule::StepDecay { step_size, gamma } => {
+                learning_rate * gamma.powf((iteration / step_size.max(1)) as f64)
+            }
+            // powi would wrap the iteration beyond i32::MAX
+            LrSchedule::Exponential { gamma } => learning_rate * gamma.powf(iteration as f64),
+            LrSchedule::CosineWarmRestarts {
+                period,
+                period_mult,
+                min_lr,
+            } => cosine(
+                learning_rate,
+                min_lr,
+                restart_progress(iteration, period.max(1), period_mult.max(1)),
+            ),
+            LrSchedule::LinearWarmup {
+                warmup_iters,
+                start_factor,
+            } => {
+                let progress = (iteration as f64 / warmup_iters.max(1) as f64).min(1.0);
+                learning_rate * (start_factor + (1.0 - start_factor) * progress)
+            }
+            LrSchedule::OneCycle {
+                total_iters,
+                pct_start,
+                div_factor,
+                final_div_factor,
+            } => {
+                let initial_lr = learning_rate / div_factor;
+                let final_lr = initial_lr / final_div_factor;
+                let warmup_iters = ((total_iters as f64 * pct_start) as usize).max(1);
+                if iteration < warmup_iters {
+                    cosine(
+                        initial_lr,
+                        learning_rate,
+                        iteration as f64 / warmup_iters as f64,
+                    )
+                } else {
+                    let annealing_iters = total_iters.saturating_sub(warmup_iters).max(1);
+                    cosine(
+                        learning_rate,
+                        final_lr,
+                        (iteration - warmup_iters) as f64 / annealing_iters as f64,
+                    )
+                }
+            }
+        }
+    }
+
+    pub fn init(&self, learning_rate: f64) -> TaxifareLrScheduler {
+        TaxifareLrScheduler {
+            schedule: self.clone(),
+            learning_rate,
+            iteration: 0,
+        }
+    }
+}
+
+///
+/// Scheduler stepping through an [LrSchedule].
+/// Only the iteration is recorded, so a resumed run continues the schedule where it stopped.
+///
+#[derive(Clone, Debug)]
+pub struct TaxifareLrScheduler {
+    schedule: LrSchedule,
+    learning_rate: f64,
+    iteration: usize,
+}
+
+impl LrScheduler for TaxifareLrScheduler {
+    type Record<B: Backend> = usize;
+
+    fn step(&mut self) -> LearningRate {
+        let learning_rate = self
+            .schedule
+            .learning_rate(self.learning_rate, self.iteration);
+        self.iteration += 1;
+        learning_rate
+    }
+
+    fn to_record<B: Backend>(&self) -> Self::Record<B> {
+        self.iteration
+    }
+
+    fn load_record<B: Backend>(mut self, record: Self::Record<B>) -> Self {
+        self.iteration = record;
+        self
+    }
+}
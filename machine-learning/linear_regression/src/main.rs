@@ -1,26 +1,138 @@
-use burn::{
-    backend::{Autodiff, Metal},
-    optim::AdamConfig,
-    tensor::Device,
+use burn::{config::Config, tensor::backend::AutodiffBackend};
+use linear_regression::{
+    backend::{BackendKind, BackendTask},
+    models::taxifare_model::ModelConfig,
+    training::{ArtifactDirMode, CATEGORICAL_VIOLATIONS_FILE, TrainingConfig, TrainingSummary},
 };
-use linear_regression::{models::taxifare_model::ModelConfig, training::TrainingConfig};
-/*
-fn custom_init() -> burn::backend::wgpu::WgpuSetup {
-    let device = Default::default();
-    init_setup::<burn::backend::wgpu::Metal>(&device, Default::default())
+
+const USAGE: &str = "Usage: linear_regression [--config <config.json>] [--data <file>] \
+[--artifact-dir <dir>] [--resume | --force] [key=value ...]
+
+  --config        Training config saved by a previous run, defaults are used without it.
+  --data          Prepared csv file or sqlite database, defaults to TaxiFaresPrepared.csv.
+  --artifact-dir  Directory for the model, config and checkpoints, defaults to ../config.
+  --resume        Continues from the latest checkpoint in the artifact directory.
+  --force         Deletes a non-empty artifact directory instead of refusing to train.
+  key=value       Overrides a config value by its dotted key, e.g. model.dropout=0.2 or num_epochs=20.
+
+The backend is selected with the TAXIFARE_BACKEND environment variable.";
+
+/// Categorical violations printed before pointing to the full report.
+const MAX_REPORTED_VIOLATIONS: usize = 20;
+
+struct Args {
+    config_file: Option<String>,
+    data_file: String,
+    artifact_dir: String,
+    mode: ArtifactDirMode,
+    overrides: Vec<String>,
 }
-*/
+
+fn exit_with_usage(message: &str) -> ! {
+    eprintln!("{message}\n\n{USAGE}");
+    std::process::exit(2);
+}
+
+fn parse_args() -> Args {
+    let mut parsed = Args {
+        config_file: None,
+        data_file: "TaxiFaresPrepared.csv".to_string(),
+        artifact_dir: "../config".to_string(),
+        mode: ArtifactDirMode::Fresh,
+        overrides: Vec::new(),
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| exit_with_usage(&format!("Missing value of {arg}")))
+        };
+        match arg.as_str() {
+            "--config" => parsed.config_file = Some(value()),
+            "--data" => parsed.data_file = value(),
+            "--artifact-dir" => parsed.artifact_dir = value(),
+            "--resume" => parsed.mode = ArtifactDirMode::Resume,
+            "--force" => parsed.mode = ArtifactDirMode::Force,
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ if arg.contains('=') => parsed.overrides.push(arg),
+            _ => exit_with_usage(&format!("Unknown argument {arg}")),
+        }
+    }
+    parsed
+}
+
+struct Training {
+    args: Args,
+    config: TrainingConfig,
+}
+
+impl BackendTask for Training {
+    type Output = Result<TrainingSummary, std::io::Error>;
+
+    fn run<B: AutodiffBackend>(self, device: B::Device) -> Self::Output {
+        linear_regression::training::train::<B>(
+            &self.args.artifact_dir,
+            &self.args.data_file,
+            self.config,
+            self.args.mode,
+            device,
+        )
+    }
+}
+
+fn report(artifact_dir: &str, summary: &TrainingSummary) {
+    if let Some(epoch) = summary.resumed_from {
+        println!("Resumed from the checkpoint of epoch {epoch}");
+    }
+    let violations = &summary.categorical_violations;
+    if !violations.is_empty() {
+        eprintln!(
+            "{} categorical values are mapped to the unknown bucket:",
+            violations.len()
+        );
+        for violation in violations.iter().take(MAX_REPORTED_VIOLATIONS) {
+            eprintln!("  {violation}");
+        }
+        eprintln!("Full report written to {artifact_dir}/{CATEGORICAL_VIOLATIONS_FILE}");
+    }
+    for warning in &summary.warnings {
+        eprintln!("{warning}");
+    }
+    if let Some(report) = &summary.early_stopping {
+        println!(
+            "Restored the model of epoch {}: {}",
+            report.best_epoch, report.reason
+        );
+    }
+}
+
 fn main() {
-    type MyBackend = Metal<f32, i32>;
-    type MyAutodiffBackend = Autodiff<MyBackend>;
-    let device = Device::<MyBackend>::default();
-    let artifact_dir = "../config";
-    linear_regression::training::train::<MyAutodiffBackend>(
-        artifact_dir,
-        TrainingConfig::new(
-            ModelConfig::new(vec![(7, 4), (24, 12), (2, 1)], 6, &[100, 50], 0.4),
-            AdamConfig::new(),
-        ),
-        device.clone(),
+    let args = parse_args();
+    let config = match &args.config_file {
+        Some(config_file) => TrainingConfig::load(config_file).expect("Config can not be loaded."),
+        None => TrainingConfig::new(ModelConfig::new(
+            vec![(7, 4), (24, 12), (2, 1)],
+            6,
+            &[100, 50],
+            0.4,
+        )),
+    };
+    let config = config
+        .with_overrides(&args.overrides)
+        .unwrap_or_else(|err| exit_with_usage(&err.to_string()));
+
+    let backend = BackendKind::from_env().expect("Backend can not be selected.");
+    println!(
+        "Training on the {backend} backend with the {} loss",
+        config.model.loss
     );
+    let artifact_dir = args.artifact_dir.clone();
+    let summary = backend
+        .run(Training { args, config })
+        .and_then(|summary| summary)
+        .expect("Training failed.");
+    report(&artifact_dir, &summary);
 }
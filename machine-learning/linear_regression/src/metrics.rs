@@ -0,0 +1,235 @@
+use std::marker::PhantomData;
+
+use burn::{
+    prelude::*,
+    train::{
+        RegressionOutput,
+        metric::{Adaptor, Metric, MetricEntry, MetricMetadata, Numeric, NumericEntry},
+    },
+};
+use serde::{Deserialize, Serialize};
+
+/// Predictions and targets of a batch, both `[batch_size, 1]`.
+pub struct RegressionInput<B: Backend> {
+    outputs: Tensor<B, 2>,
+    targets: Tensor<B, 2>,
+}
+
+impl<B: Backend> Adaptor<RegressionInput<B>> for RegressionOutput<B> {
+    fn adapt(&self) -> RegressionInput<B> {
+        RegressionInput {
+            outputs: self.output.clone(),
+            targets: self.targets.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegressionMetricKind {
+    /// Root mean squared error.
+    Rmse,
+    /// Mean absolute error.
+    Mae,
+    /// Coefficient of determination.
+    R2,
+    /// Mean absolute percentage error, items with a target of 0 are skipped.
+    Mape,
+    /// Median absolute error.
+    MedianAe,
+}
+
+impl RegressionMetricKind {
+    fn name(&self) -> &'static str {
+        match self {
+            RegressionMetricKind::Rmse => "RMSE",
+            RegressionMetricKind::Mae => "MAE",
+            RegressionMetricKind::R2 => "R2",
+            RegressionMetricKind::Mape => "MAPE",
+            RegressionMetricKind::MedianAe => "Median AE",
+        }
+    }
+}
+
+///
+/// Running sums over all items, from which the metrics are computed exactly.
+/// Absolute errors are only collected if the median is needed.
+///
+#[derive(Clone, Debug, Default)]
+pub struct ErrorStatistics {
+    count: usize,
+    squared_error: f64,
+    absolute_error: f64,
+    percentage_count: usize,
+    absolute_percentage_error: f64,
+    target: f64,
+    squared_target: f64,
+    absolute_errors: Option<Vec<f64>>,
+}
+
+/// All metrics of a set of predictions.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RegressionScores {
+    pub count: usize,
+    pub rmse: f64,
+    pub mae: f64,
+    pub r2: f64,
+    pub mape: f64,
+    pub median_ae: f64,
+}
+
+impl ErrorStatistics {
+    pub fn new(with_median: bool) -> Self {
+        Self {
+            absolute_errors: with_median.then(Vec::new),
+            ..Default::default()
+        }
+    }
+
+    pub fn add(&mut self, output: f64, target: f64) {
+        let error = output - target;
+        self.count += 1;
+        self.squared_error += error * error;
+        self.absolute_error += error.abs();
+        if target != 0.0 {
+            self.percentage_count += 1;
+            self.absolute_percentage_error += (error / target).abs();
+        }
+        self.target += target;
+        self.squared_target += target * target;
+        if let Some(absolute_errors) = &mut self.absolute_errors {
+            absolute_errors.push(error.abs());
+        }
+    }
+
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Value of the metric, 0 if there are no items.
+    /// The median is only available if the statistics were created with it.
+    pub fn value(&mut self, kind: RegressionMetricKind) -> f64 {
+        let count = self.count.max(1) as f64;
+        match kind {
+            RegressionMetricKind::Rmse => (self.squared_error / count).sqrt(),
+            RegressionMetricKind::Mae => self.absolute_error / count,
+            RegressionMetricKind::R2 => {
+                let total_sum_of_squares = self.squared_target - self.target * self.target / count;
+                if total_sum_of_squares > 0.0 {
+                    1.0 - self.squared_error / total_sum_of_squares
+                } else {
+                    0.0
+                }
+            }
+            RegressionMetricKind::Mape => {
+                100.0 * self.absolute_percentage_error / self.percentage_count.max(1) as f64
+            }
+            RegressionMetricKind::MedianAe => match self.absolute_errors.as_deref_mut() {
+                Some([]) | None => 0.0,
+                Some(absolute_errors) => {
+                    let middle = absolute_errors.len() / 2;
+                    *absolute_errors
+                        .select_nth_unstable_by(middle, f64::total_cmp)
+                        .1
+                }
+            },
+        }
+    }
+
+    pub fn scores(&mut self) -> RegressionScores {
+        RegressionScores {
+            count: self.count,
+            rmse: self.value(RegressionMetricKind::Rmse),
+            mae: self.value(RegressionMetricKind::Mae),
+            r2: self.value(RegressionMetricKind::R2),
+            mape: self.value(RegressionMetricKind::Mape),
+            median_ae: self.value(RegressionMetricKind::MedianAe),
+        }
+    }
+}
+
+///
+/// Regression metric over all items seen in the epoch so far.
+/// The metric is computed from sums over the items instead of averaging per batch values,
+/// so the value after the last batch is exact for the epoch, also for R² and the median.
+///
+/// Burn aggregates the entries of an epoch by their weighted mean. Every entry holds the
+/// change of `value * count` caused by its batch, so the mean over the entries of the
+/// epoch is the exact epoch value. For additive metrics like MAE this is the batch sum.
+///
+pub struct RegressionMetric<B: Backend> {
+    kind: RegressionMetricKind,
+    statistics: ErrorStatistics,
+    value: f64,
+    backend: PhantomData<B>,
+}
+
+impl<B: Backend> RegressionMetric<B> {
+    pub fn new(kind: RegressionMetricKind) -> Self {
+        Self {
+            kind,
+            statistics: ErrorStatistics::new(kind == RegressionMetricKind::MedianAe),
+            value: 0.0,
+            backend: PhantomData,
+        }
+    }
+
+    pub fn rmse() -> Self {
+        Self::new(RegressionMetricKind::Rmse)
+    }
+
+    pub fn mae() -> Self {
+        Self::new(RegressionMetricKind::Mae)
+    }
+
+    pub fn r2() -> Self {
+        Self::new(RegressionMetricKind::R2)
+    }
+
+    pub fn mape() -> Self {
+        Self::new(RegressionMetricKind::Mape)
+    }
+
+    pub fn median_absolute_error() -> Self {
+        Self::new(RegressionMetricKind::MedianAe)
+    }
+}
+
+impl<B: Backend> Metric for RegressionMetric<B> {
+    type Input = RegressionInput<B>;
+
+    fn update(&mut self, input: &RegressionInput<B>, _metadata: &MetricMetadata) -> MetricEntry {
+        let previous_total = self.value * self.statistics.count() as f64;
+        let previous_count = self.statistics.count();
+        let outputs = input.outputs.clone().into_data();
+        let targets = input.targets.clone().into_data();
+        for (output, target) in outputs.iter::<f64>().zip(targets.iter::<f64>()) {
+            self.statistics.add(output, target);
+        }
+        self.value = self.statistics.value(self.kind);
+        let entry = NumericEntry::Aggregated {
+            sum: self.value * self.statistics.count() as f64 - previous_total,
+            count: self.statistics.count() - previous_count,
+        };
+
+        let formatted = match self.kind {
+            RegressionMetricKind::Mape => format!("epoch {:.2} %", self.value),
+            _ => format!("epoch {:.4}", self.value),
+        };
+        MetricEntry::new(self.name(), formatted, entry.serialize())
+    }
+
+    fn clear(&mut self) {
+        self.statistics = ErrorStatistics::new(self.kind == RegressionMetricKind::MedianAe);
+        self.value = 0.0;
+    }
+
+    fn name(&self) -> String {
+        self.kind.name().to_string()
+    }
+}
+
+impl<B: Backend> Numeric for RegressionMetric<B> {
+    fn value(&self) -> f64 {
+        self.value
+    }
+}
@@ -25,8 +25,9 @@ impl TaxifareEmbeddingLayerConfig {
         Self {
             embedding_configs: embedding_sizes
                 .iter()
+                // One additional row is reserved for unknown values.
                 .map(|(num_features, num_embeddings)| {
-                    EmbeddingConfig::new(*num_features, *num_embeddings)
+                    EmbeddingConfig::new(*num_features + 1, *num_embeddings)
                 })
                 .collect(),
             dropout: DropoutConfig::new(dropout_rate),
@@ -59,7 +60,8 @@ impl<B: Backend> TaxifareEmbeddingModel<B> {
             .embeddings
             .iter()
             .enumerate()
-            .map(|(i, embed)| embed.forward(input[i].clone()).squeeze(0))
+            // [batch_size, 1] -> [batch_size, 1, d_model] -> [batch_size, d_model]
+            .map(|(i, embed)| embed.forward(input[i].clone()).squeeze(1))
             .collect::<Vec<Tensor<B, 2>>>();
         let raw_output = Tensor::cat(x, 1);
         self.dropout_layer.forward(raw_output)
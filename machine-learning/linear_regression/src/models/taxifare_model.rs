@@ -1,25 +1,37 @@
-use crate::batcher::TaxifareBatch;
+use std::sync::Arc;
+
+use crate::batcher::{TaxifareBatch, TaxifareInferenceBatch};
+use crate::loss::LossFunction;
 
 use super::embedding_model::{TaxifareEmbeddingLayerConfig, TaxifareEmbeddingModel};
 use super::linear_model::{TaxifareLinearLayerConfig, TaxifareLinearLayerModel};
+use burn::data::dataloader::DataLoader;
+use burn::module::Ignored;
 use burn::nn::BatchNorm;
 use burn::nn::BatchNormConfig;
 use burn::nn::Linear;
 use burn::nn::LinearConfig;
-use burn::nn::loss::MseLoss;
-use burn::nn::loss::Reduction;
 use burn::prelude::*;
 use burn::tensor::backend::AutodiffBackend;
 use burn::train::{RegressionOutput, TrainOutput, TrainStep, ValidStep};
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Serialize, Deserialize)]
+///
+/// Hyperparameters of the model, the layers are derived from them on [ModelConfig::init].
+///
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ModelConfig {
-    embedding_config: TaxifareEmbeddingLayerConfig,
-    linear_layers_config: Vec<TaxifareLinearLayerConfig>,
-    output_layer_config: LinearConfig,
-    cont_norm_layer_config: BatchNormConfig,
+    /// Number of known values and embedding size per categorical feature.
+    pub embedding_sizes: Vec<(usize, usize)>,
+    /// Number of continuous features.
+    pub n_cont: usize,
+    /// Outputs of the hidden linear layers.
+    pub layers: Vec<usize>,
+    pub dropout: f64,
+    /// Configs saved before the loss was configurable were trained with MSE.
+    #[serde(default)]
+    pub loss: LossFunction,
 }
 
 impl ModelConfig {
@@ -27,39 +39,61 @@ impl ModelConfig {
         embedding_sizes: Vec<(usize, usize)>,
         n_cont: usize,
         layers: &[usize],
-        dropout_rate: f64,
+        dropout: f64,
     ) -> Self {
-        let mut layer_configuration = layers.to_vec();
-        layer_configuration.insert(
-            0,
-            n_cont + embedding_sizes.iter().map(|ebsz| ebsz.1).sum::<usize>(),
-        );
-        println!("Resulting layer configuration{layer_configuration:?}");
         Self {
-            embedding_config: TaxifareEmbeddingLayerConfig::new(embedding_sizes, dropout_rate),
-            linear_layers_config: layer_configuration
-                .iter()
-                .tuple_windows()
-                .map(|(input, output)| {
-                    TaxifareLinearLayerConfig::new(*input, *output, dropout_rate)
-                })
-                .collect(),
-            output_layer_config: LinearConfig::new(*layers.last().unwrap(), 1),
-            cont_norm_layer_config: BatchNormConfig::new(n_cont),
+            embedding_sizes,
+            n_cont,
+            layers: layers.to_vec(),
+            dropout,
+            loss: LossFunction::default(),
         }
     }
 
+    /// Fails if the parameters of the loss are invalid, see [LossFunction::validated].
+    pub fn with_loss(mut self, loss: LossFunction) -> Result<Self, std::io::Error> {
+        self.loss = loss.validated()?;
+        Ok(self)
+    }
+
+    /// Number of known values per categorical feature in the order of the embeddings.
+    pub fn categorical_cardinalities(&self) -> Vec<usize> {
+        self.embedding_sizes
+            .iter()
+            .map(|(cardinality, _)| *cardinality)
+            .collect()
+    }
+
     /// Returns the initialized model.
     pub fn init<B: Backend>(&self, device: &B::Device) -> Model<B> {
+        let mut layer_configuration = self.layers.clone();
+        layer_configuration.insert(
+            0,
+            // The continuous features are followed by their missing value mask
+            2 * self.n_cont
+                + self
+                    .embedding_sizes
+                    .iter()
+                    .map(|ebsz| ebsz.1)
+                    .sum::<usize>(),
+        );
+
         Model {
-            embedding: self.embedding_config.init(device),
-            linear_layers: self
-                .linear_layers_config
+            embedding: TaxifareEmbeddingLayerConfig::new(
+                self.embedding_sizes.clone(),
+                self.dropout,
+            )
+            .init(device),
+            linear_layers: layer_configuration
                 .iter()
-                .map(|config| config.init(device))
+                .tuple_windows()
+                .map(|(input, output)| {
+                    TaxifareLinearLayerConfig::new(*input, *output, self.dropout).init(device)
+                })
                 .collect(),
-            output_layer: self.output_layer_config.init::<B>(device),
-            cont_input_norm_layer: self.cont_norm_layer_config.init::<B, 1>(device),
+            output_layer: LinearConfig::new(*self.layers.last().unwrap(), 1).init::<B>(device),
+            cont_input_norm_layer: BatchNormConfig::new(self.n_cont).init::<B, 1>(device),
+            loss: Ignored(self.loss),
         }
     }
 }
@@ -70,35 +104,58 @@ pub struct Model<B: Backend> {
     linear_layers: Vec<TaxifareLinearLayerModel<B>>,
     cont_input_norm_layer: BatchNorm<B, 1>,
     output_layer: Linear<B>,
+    /// Not part of the record, it is taken from the config.
+    loss: Ignored<LossFunction>,
 }
 
 impl<B: Backend> Model<B> {
     /// # Shapes
-    ///   - Images [batch_size, height, width]
-    ///   - Output [batch_size, num_classes]
+    ///   - Categorical input [batch_size, 1] per embedding
+    ///   - Continuous input [batch_size, n_cont]
+    ///   - Continuous mask [batch_size, n_cont], 0 where the continuous input is missing
+    ///   - Output [batch_size, 1]
     pub fn forward(
         &self,
         cat_input: Vec<Tensor<B, 2, Int>>,
         cont_input: Tensor<B, 2>,
+        cont_mask: Tensor<B, 2>,
     ) -> Tensor<B, 2> {
         let cat_output = self.embedding.forward(cat_input);
         let cont_norm_input = self.cont_input_norm_layer.forward(cont_input);
-        let mut x = Tensor::cat(vec![cont_norm_input, cat_output], 1);
+        let mut x = Tensor::cat(vec![cont_norm_input, cont_mask, cat_output], 1);
         for layer in &self.linear_layers {
             x = layer.forward(x);
         }
-        x = self.output_layer.forward(x);
-        x.squeeze(0)
+        self.output_layer.forward(x)
+    }
+
+    /// Predicted fares of all rows of the loader, in loader order.
+    pub fn predict_batch(
+        &self,
+        dataloader: Arc<dyn DataLoader<B, TaxifareInferenceBatch<B>>>,
+    ) -> Vec<f32> {
+        dataloader
+            .iter()
+            .flat_map(|batch| {
+                self.forward(batch.cat_features, batch.cont_features, batch.cont_mask)
+                    .into_data()
+                    .to_vec::<f32>()
+                    .expect("Model output should be a float tensor.")
+            })
+            .collect()
     }
 
+    /// The loss of the output is the [LossFunction] of the config.
     pub fn forward_regression(
         &self,
         cat_input: Vec<Tensor<B, 2, Int>>,
         cont_input: Tensor<B, 2>,
+        cont_mask: Tensor<B, 2>,
         targets: Tensor<B, 2>,
+        weights: Option<Tensor<B, 2>>,
     ) -> RegressionOutput<B> {
-        let output = self.forward(cat_input, cont_input);
-        let loss = MseLoss::new().forward(output.clone(), targets.clone(), Reduction::Mean);
+        let output = self.forward(cat_input, cont_input, cont_mask);
+        let loss = self.loss.forward(output.clone(), targets.clone(), weights);
 
         RegressionOutput {
             loss,
@@ -110,7 +167,13 @@ impl<B: Backend> Model<B> {
 
 impl<B: AutodiffBackend> TrainStep<TaxifareBatch<B>, RegressionOutput<B>> for Model<B> {
     fn step(&self, item: TaxifareBatch<B>) -> TrainOutput<RegressionOutput<B>> {
-        let item = self.forward_regression(item.cat_features, item.cont_features, item.predictions);
+        let item = self.forward_regression(
+            item.cat_features,
+            item.cont_features,
+            item.cont_mask,
+            item.predictions,
+            item.weights,
+        );
 
         TrainOutput::new(self, item.loss.backward(), item)
     }
@@ -118,6 +181,12 @@ impl<B: AutodiffBackend> TrainStep<TaxifareBatch<B>, RegressionOutput<B>> for Mo
 
 impl<B: Backend> ValidStep<TaxifareBatch<B>, RegressionOutput<B>> for Model<B> {
     fn step(&self, item: TaxifareBatch<B>) -> RegressionOutput<B> {
-        self.forward_regression(item.cat_features, item.cont_features, item.predictions)
+        self.forward_regression(
+            item.cat_features,
+            item.cont_features,
+            item.cont_mask,
+            item.predictions,
+            item.weights,
+        )
     }
 }
@@ -0,0 +1,51 @@
+use burn::{
+    optim::{AdamConfig, AdamWConfig, Optimizer, RmsPropConfig, SgdConfig},
+    tensor::backend::AutodiffBackend,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::taxifare_model::Model;
+
+///
+/// Optimizer used for training, configured with the hyperparameters of Burn's optimizer configs.
+/// Every variant has a weight decay and an optional gradient clipping by norm or value,
+/// e.g. `optimizer.Adam.grad_clipping={"Norm":1.0}`.
+///
+#[derive(Clone, Serialize, Deserialize)]
+pub enum OptimizerConfig {
+    /// Momentum and Nesterov acceleration are set with `momentum`,
+    /// e.g. `{"momentum":0.9,"dampening":0.0,"nesterov":true}`.
+    Sgd(SgdConfig),
+    Adam(AdamConfig),
+    /// Adam with weight decay decoupled from the gradient.
+    AdamW(AdamWConfig),
+    RmsProp(RmsPropConfig),
+}
+
+impl Default for OptimizerConfig {
+    fn default() -> Self {
+        OptimizerConfig::Adam(AdamConfig::new())
+    }
+}
+
+/// Work which is generic over the optimizer, see [OptimizerConfig::run].
+pub trait OptimizerTask<B: AutodiffBackend> {
+    type Output;
+
+    fn run<O>(self, optimizer: O) -> Self::Output
+    where
+        O: Optimizer<Model<B>, B>,
+        O::Record: 'static;
+}
+
+impl OptimizerConfig {
+    /// Runs the task with the initialized optimizer, the optimizers have different types.
+    pub fn run<B: AutodiffBackend, T: OptimizerTask<B>>(&self, task: T) -> T::Output {
+        match self {
+            OptimizerConfig::Sgd(config) => task.run(config.init::<B, Model<B>>()),
+            OptimizerConfig::Adam(config) => task.run(config.init::<B, Model<B>>()),
+            OptimizerConfig::AdamW(config) => task.run(config.init::<B, Model<B>>()),
+            OptimizerConfig::RmsProp(config) => task.run(config.init::<B, Model<B>>()),
+        }
+    }
+}
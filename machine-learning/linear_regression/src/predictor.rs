@@ -0,0 +1,103 @@
+use burn::prelude::Backend;
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use taxifare_features::TripFeatures;
+
+use crate::{
+    dataset::{mapped_dataset::RawDatafieldToFeaturesMapper, raw_dataset::TaxifareDatasetRawItem},
+    inference::{load_model, predict},
+    models::taxifare_model::Model,
+    training::TrainingConfig,
+};
+
+/// A trip as it is requested, before any feature engineering.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Trip {
+    pub pickup_latitude: f64,
+    pub pickup_longitude: f64,
+    pub dropoff_latitude: f64,
+    pub dropoff_longitude: f64,
+    /// Pickup time in any timezone, e.g. `2010-04-19T08:17:56-04:00`.
+    pub pickup_datetime: DateTime<FixedOffset>,
+    pub passenger_count: f64,
+}
+
+impl Trip {
+    /// Features as computed by [TripFeatures], the same definitions used to prepare the
+    /// training data. The fare is unknown and set to 0.
+    pub fn to_raw_item(&self) -> TaxifareDatasetRawItem {
+        let features = TripFeatures::new(
+            self.pickup_latitude,
+            self.pickup_longitude,
+            self.dropoff_latitude,
+            self.dropoff_longitude,
+            &self.pickup_datetime,
+        );
+        TaxifareDatasetRawItem {
+            fare_amount: 0.0,
+            pickup_latitude: self.pickup_latitude,
+            pickup_longitude: self.pickup_longitude,
+            dropoff_latitude: self.dropoff_latitude,
+            dropoff_longitude: self.dropoff_longitude,
+            passenger_count: Some(self.passenger_count),
+            distance: Some(features.distance),
+            pickup_hour: features.time.pickup_hour,
+            pickup_weekday: features.time.pickup_weekday,
+            am_or_pm: features.time.am_or_pm,
+            pickup_timestamp: Some(features.time.pickup_timestamp),
+            sample_weight: None,
+        }
+    }
+}
+
+///
+/// Predicts fares of raw trips with the model of an artifact directory.
+/// The model runs on the given backend, which should not be an autodiff backend.
+///
+pub struct TaxifarePredictor<B: Backend> {
+    config: TrainingConfig,
+    model: Model<B>,
+    mapper: RawDatafieldToFeaturesMapper,
+    /// Largest number of trips in one forward pass.
+    batch_size: usize,
+    device: B::Device,
+}
+
+impl<B: Backend> TaxifarePredictor<B> {
+    pub fn load(artifact_dir: &str, device: B::Device) -> Result<Self, std::io::Error> {
+        let (config, model, mapper) = load_model::<B>(artifact_dir, &device)?;
+        Ok(Self {
+            batch_size: config.batch_size,
+            config,
+            model,
+            mapper,
+            device,
+        })
+    }
+
+    /// Defaults to the batch size of the training config.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size;
+        self
+    }
+
+    /// Training config of the loaded model.
+    pub fn config(&self) -> &TrainingConfig {
+        &self.config
+    }
+
+    pub fn predict(&self, trip: &Trip) -> f32 {
+        self.predict_batch(std::slice::from_ref(trip))[0]
+    }
+
+    /// Predicted fares in the order of the trips.
+    pub fn predict_batch(&self, trips: &[Trip]) -> Vec<f32> {
+        predict(
+            &self.model,
+            &self.mapper,
+            trips.iter().map(Trip::to_raw_item),
+            self.batch_size,
+            &self.device,
+        )
+    }
+}
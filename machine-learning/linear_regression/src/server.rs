@@ -0,0 +1,256 @@
+//! JSON over HTTP fare estimates of a [TaxifarePredictor].
+
+use std::{io::ErrorKind, sync::Arc};
+
+use axum::{
+    Json, Router,
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+};
+use burn::prelude::Backend;
+use serde::Serialize;
+use serde_json::{Value, json};
+
+use crate::{
+    early_stopping::EarlyStoppingReport,
+    predictor::{TaxifarePredictor, Trip},
+    training::TrainingConfig,
+};
+
+pub mod batching;
+
+use batching::{BatchingConfig, BatchingMetrics, RequestBatcher};
+
+/// Largest number of trips accepted by a single request.
+pub const MAX_BATCH_TRIPS: usize = 10_000;
+
+/// Returned by `GET /model`.
+#[derive(Clone, Debug, Serialize)]
+pub struct ModelInfo {
+    pub artifact_dir: String,
+    pub config: TrainingConfig,
+    /// Only present if the model was trained with early stopping.
+    pub early_stopping: Option<EarlyStoppingReport>,
+}
+
+pub struct ServerState {
+    batcher: RequestBatcher,
+    info: ModelInfo,
+}
+
+impl ServerState {
+    pub fn load<B: Backend>(
+        artifact_dir: &str,
+        device: B::Device,
+        batching: BatchingConfig,
+    ) -> Result<Self, std::io::Error> {
+        let predictor = TaxifarePredictor::<B>::load(artifact_dir, device)?;
+        let early_stopping = match EarlyStoppingReport::load(artifact_dir) {
+            Ok(report) => Some(report),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+        let info = ModelInfo {
+            artifact_dir: artifact_dir.to_string(),
+            config: predictor.config().clone(),
+            early_stopping,
+        };
+        Ok(Self {
+            batcher: RequestBatcher::spawn(predictor, batching),
+            info,
+        })
+    }
+}
+
+///
+/// Routes of the inference server:
+/// - `POST /predict` takes a trip or an array of trips and returns `{"fare": ..}` or `{"fares": [..]}`
+/// - `GET /health`
+/// - `GET /model` returns the [ModelInfo]
+/// - `GET /metrics` returns the [BatchingMetrics]
+///
+pub fn router(state: ServerState) -> Router {
+    Router::new()
+        .route("/predict", post(predict))
+        .route("/health", get(health))
+        .route("/model", get(model))
+        .route("/metrics", get(metrics))
+        .with_state(Arc::new(state))
+}
+
+/// Problem with a single value of a request.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct FieldError {
+    /// Position of the trip in a batch request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<&'static str>,
+    pub message: String,
+}
+
+///
+/// Error response with a JSON body of the form
+/// `{"error": {"code": "invalid_trip", "message": "..", "details": [..]}}`.
+///
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+    details: Vec<FieldError>,
+}
+
+impl ApiError {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+            details: Vec::new(),
+        }
+    }
+
+    fn invalid_trips(details: Vec<FieldError>) -> Self {
+        Self {
+            details,
+            ..Self::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_trip",
+                "The request contains invalid trips.",
+            )
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": {
+                "code": self.code,
+                "message": self.message,
+                "details": self.details,
+            }
+        });
+        (self.status, Json(body)).into_response()
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PredictResponse {
+    Single { fare: f32 },
+    Batch { fares: Vec<f32> },
+}
+
+async fn predict(
+    State(state): State<Arc<ServerState>>,
+    body: Bytes,
+) -> Result<Json<PredictResponse>, ApiError> {
+    let (trips, is_batch) = parse_trips(&body)?;
+    let fares = state.batcher.predict(trips).await.map_err(|err| {
+        ApiError::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "prediction_failed",
+            err.to_string(),
+        )
+    })?;
+
+    Ok(Json(if is_batch {
+        PredictResponse::Batch { fares }
+    } else {
+        PredictResponse::Single { fare: fares[0] }
+    }))
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn model(State(state): State<Arc<ServerState>>) -> Json<ModelInfo> {
+    Json(state.info.clone())
+}
+
+async fn metrics(State(state): State<Arc<ServerState>>) -> Json<BatchingMetrics> {
+    Json(state.batcher.metrics())
+}
+
+/// Trips of the request body and whether it was an array.
+fn parse_trips(body: &[u8]) -> Result<(Vec<Trip>, bool), ApiError> {
+    let value: Value = serde_json::from_slice(body)
+        .map_err(|err| ApiError::new(StatusCode::BAD_REQUEST, "invalid_json", err.to_string()))?;
+    let (values, is_batch) = match value {
+        Value::Array(values) => (values, true),
+        value @ Value::Object(_) => (vec![value], false),
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "invalid_trip",
+                "Expected a trip object or an array of trips.",
+            ));
+        }
+    };
+    if values.is_empty() {
+        return Err(ApiError::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "empty_batch",
+            "The array of trips is empty.",
+        ));
+    }
+    if values.len() > MAX_BATCH_TRIPS {
+        return Err(ApiError::new(
+            StatusCode::PAYLOAD_TOO_LARGE,
+            "batch_too_large",
+            format!("At most {MAX_BATCH_TRIPS} trips can be predicted at once."),
+        ));
+    }
+
+    let mut trips = Vec::with_capacity(values.len());
+    let mut errors = Vec::new();
+    for (index, value) in values.into_iter().enumerate() {
+        let index = is_batch.then_some(index);
+        match serde_json::from_value::<Trip>(value) {
+            Ok(trip) => {
+                errors.extend(
+                    validate(&trip)
+                        .into_iter()
+                        .map(|(field, message)| FieldError {
+                            index,
+                            field: Some(field),
+                            message,
+                        }),
+                );
+                trips.push(trip);
+            }
+            Err(err) => errors.push(FieldError {
+                index,
+                field: None,
+                message: err.to_string(),
+            }),
+        }
+    }
+    if errors.is_empty() {
+        Ok((trips, is_batch))
+    } else {
+        Err(ApiError::invalid_trips(errors))
+    }
+}
+
+/// Fields of the trip which are out of range.
+fn validate(trip: &Trip) -> Vec<(&'static str, String)> {
+    let mut errors = Vec::new();
+    let mut check = |field, value: f64, min: f64, max: f64| {
+        if !(min..=max).contains(&value) {
+            errors.push((field, format!("{value} is not in [{min}, {max}]")));
+        }
+    };
+    check("pickup_latitude", trip.pickup_latitude, -90.0, 90.0);
+    check("dropoff_latitude", trip.dropoff_latitude, -90.0, 90.0);
+    check("pickup_longitude", trip.pickup_longitude, -180.0, 180.0);
+    check("dropoff_longitude", trip.dropoff_longitude, -180.0, 180.0);
+    check("passenger_count", trip.passenger_count, 0.0, f64::MAX);
+    errors
+}
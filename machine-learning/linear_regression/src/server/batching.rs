@@ -0,0 +1,200 @@
+use std::{
+    panic::AssertUnwindSafe,
+    sync::{
+        Arc, Mutex, PoisonError,
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    time::{Duration, Instant},
+};
+
+use burn::prelude::Backend;
+use serde::Serialize;
+use tokio::sync::oneshot;
+
+use crate::predictor::{TaxifarePredictor, Trip};
+
+/// Upper bounds of the latency buckets in milliseconds.
+const LATENCY_BOUNDS_MS: [f64; 12] = [
+    0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0, 1000.0, 5000.0,
+];
+/// Upper bounds of the batch size buckets.
+const BATCH_SIZE_BOUNDS: [f64; 12] = [
+    1.0, 2.0, 4.0, 8.0, 16.0, 32.0, 64.0, 128.0, 256.0, 512.0, 1024.0, 2048.0,
+];
+
+///
+/// Concurrent requests are queued and predicted together in micro-batches.
+/// A micro-batch is run as soon as it holds `max_batch_size` trips or its first
+/// request waited `max_wait`.
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BatchingConfig {
+    pub max_batch_size: usize,
+    pub max_wait: Duration,
+}
+
+impl Default for BatchingConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 256,
+            max_wait: Duration::from_millis(5),
+        }
+    }
+}
+
+/// Histogram with fixed buckets.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Histogram {
+    /// Inclusive upper bounds of the buckets.
+    pub bounds: Vec<f64>,
+    /// Count of every bucket, the last one counts the values above all bounds.
+    pub counts: Vec<u64>,
+    pub count: u64,
+    pub sum: f64,
+}
+
+impl Histogram {
+    pub fn new(bounds: &[f64]) -> Self {
+        Self {
+            bounds: bounds.to_vec(),
+            counts: vec![0; bounds.len() + 1],
+            count: 0,
+            sum: 0.0,
+        }
+    }
+
+    pub fn record(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.count += 1;
+        self.sum += value;
+    }
+}
+
+/// Returned by `GET /metrics` to tune the [BatchingConfig].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct BatchingMetrics {
+    pub max_batch_size: usize,
+    pub max_wait_ms: f64,
+    /// Time from queueing a request until its fares are predicted.
+    pub latency_ms: Histogram,
+    /// Trips per forward pass, a request larger than `max_batch_size` takes several of them.
+    pub batch_size: Histogram,
+}
+
+struct Job {
+    trips: Vec<Trip>,
+    queued_at: Instant,
+    reply: oneshot::Sender<Vec<f32>>,
+}
+
+///
+/// Handle to a worker thread which owns the predictor.
+/// Dropping the handle stops the worker once the queue is drained.
+///
+pub struct RequestBatcher {
+    sender: Sender<Job>,
+    metrics: Arc<Mutex<BatchingMetrics>>,
+}
+
+impl RequestBatcher {
+    pub fn spawn<B: Backend>(predictor: TaxifarePredictor<B>, config: BatchingConfig) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let metrics = Arc::new(Mutex::new(BatchingMetrics {
+            max_batch_size: config.max_batch_size,
+            max_wait_ms: config.max_wait.as_secs_f64() * 1000.0,
+            latency_ms: Histogram::new(&LATENCY_BOUNDS_MS),
+            batch_size: Histogram::new(&BATCH_SIZE_BOUNDS),
+        }));
+        let worker_metrics = metrics.clone();
+        let predictor = predictor.with_batch_size(config.max_batch_size);
+        std::thread::Builder::new()
+            .name("request-batcher".to_string())
+            .spawn(move || run_worker(predictor, config, receiver, worker_metrics))
+            .expect("Batching worker can not be spawned.");
+        Self { sender, metrics }
+    }
+
+    /// Fares of the trips, predicted together with the trips of concurrent requests.
+    pub async fn predict(&self, trips: Vec<Trip>) -> Result<Vec<f32>, std::io::Error> {
+        let (reply, fares) = oneshot::channel();
+        let job = Job {
+            trips,
+            queued_at: Instant::now(),
+            reply,
+        };
+        self.sender
+            .send(job)
+            .map_err(|_| std::io::Error::other("Batching worker has stopped."))?;
+        fares
+            .await
+            .map_err(|_| std::io::Error::other("Prediction of the batch failed."))
+    }
+
+    pub fn metrics(&self) -> BatchingMetrics {
+        self.metrics
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+}
+
+fn run_worker<B: Backend>(
+    predictor: TaxifarePredictor<B>,
+    config: BatchingConfig,
+    receiver: Receiver<Job>,
+    metrics: Arc<Mutex<BatchingMetrics>>,
+) {
+    // A job which did not fit into the previous micro-batch starts the next one.
+    let mut pending = None;
+    while let Some(first) = pending.take().or_else(|| receiver.recv().ok()) {
+        let deadline = first.queued_at + config.max_wait;
+        let mut batch_size = first.trips.len();
+        let mut jobs = vec![first];
+        while batch_size < config.max_batch_size {
+            let job =
+                match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(job) => job,
+                    Err(RecvTimeoutError::Timeout | RecvTimeoutError::Disconnected) => break,
+                };
+            if batch_size + job.trips.len() > config.max_batch_size {
+                pending = Some(job);
+                break;
+            }
+            batch_size += job.trips.len();
+            jobs.push(job);
+        }
+
+        let trips = jobs
+            .iter()
+            .flat_map(|job| job.trips.iter().cloned())
+            .collect::<Vec<_>>();
+        // One forward pass per chunk, so every pass is recorded with its size.
+        let chunks = trips
+            .chunks(config.max_batch_size.max(1))
+            .collect::<Vec<_>>();
+        // A panicking forward pass only fails the requests of its micro-batch.
+        let Ok(fares) = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            chunks
+                .iter()
+                .flat_map(|chunk| predictor.predict_batch(chunk))
+                .collect::<Vec<_>>()
+        })) else {
+            continue;
+        };
+
+        let mut metrics = metrics.lock().unwrap_or_else(PoisonError::into_inner);
+        for chunk in &chunks {
+            metrics.batch_size.record(chunk.len() as f64);
+        }
+        let mut fares = fares.into_iter();
+        for job in jobs {
+            metrics
+                .latency_ms
+                .record(job.queued_at.elapsed().as_secs_f64() * 1000.0);
+            let job_fares = fares.by_ref().take(job.trips.len()).collect();
+            // The caller may have gone away in the meantime.
+            let _ = job.reply.send(job_fares);
+        }
+    }
+}
@@ -1,16 +1,34 @@
+use std::{path::Path, sync::Arc};
+
 use burn::{
     config::Config,
-    data::dataloader::DataLoaderBuilder,
+    data::dataloader::{DataLoader, DataLoaderBuilder},
     module::Module,
-    optim::AdamConfig,
-    record::CompactRecorder,
+    optim::Optimizer,
+    record::{CompactRecorder, Recorder},
     tensor::backend::AutodiffBackend,
-    train::{LearnerBuilder, metric::LossMetric},
+    train::{
+        LearnerBuilder,
+        checkpoint::{ComposedCheckpointingStrategy, KeepLastNCheckpoints},
+        metric::{LearningRateMetric, LossMetric},
+    },
 };
+use serde_json::Value;
 
 use crate::{
-    batcher::TaxifareBatcher, dataset::taxifare_dataset::TaxifareDatasetBuilder,
-    models::taxifare_model::ModelConfig,
+    batcher::{TaxifareBatch, TaxifareBatcher},
+    dataset::{
+        mapped_dataset::{CategoricalViolation, RawDatafieldToFeaturesMapper},
+        raw_dataset::is_sqlite_file,
+        sample_weights::SampleWeighting,
+        scaler::ScalerKind,
+        taxifare_dataset::{SplitConfig, TaxifareDatasetBuilder},
+    },
+    early_stopping::{EarlyStopping, EarlyStoppingConfig, EarlyStoppingReport},
+    lr_schedule::LrSchedule,
+    metrics::RegressionMetric,
+    models::taxifare_model::{Model, ModelConfig},
+    optimizer::{OptimizerConfig, OptimizerTask},
 };
 
 #[derive(Config)]
@@ -27,29 +45,263 @@ pub struct TrainingConfig {
     #[config(default = 256)]
     pub batch_size: usize,
 
+    /// Base learning rate, which the schedule is applied to.
     #[config(default = 1.0e-4)]
     pub learning_rate: f64,
 
+    #[config(default = "LrSchedule::Constant")]
+    pub lr_schedule: LrSchedule,
+
+    #[config(default = "ScalerKind::Standard")]
+    pub scaler: ScalerKind,
+
+    #[config(default = "SampleWeighting::Uniform")]
+    pub sample_weighting: SampleWeighting,
+
+    #[config(default = "SplitConfig::new()")]
+    pub split: SplitConfig,
+
+    /// Stops training early and restores the best epoch, trains all epochs if not set.
+    #[config(default = "None")]
+    pub early_stopping: Option<EarlyStoppingConfig>,
+
+    /// Reuse the mapped features of previous runs on the same data.
+    /// The cache is held in memory, so by default it is only used for csv files,
+    /// which are loaded into memory anyway, and not for sqlite databases.
+    #[config(default = "None")]
+    pub feature_cache: Option<bool>,
+
+    #[config(default = "OptimizerConfig::default()")]
+    pub optimizer: OptimizerConfig,
+
     pub model: ModelConfig,
-    pub optimizer: AdamConfig,
 }
 
-fn create_artifact_dir(artifact_dir: &str) {
-    std::fs::remove_dir_all(artifact_dir).ok();
-    std::fs::create_dir_all(artifact_dir).ok();
+impl TrainingConfig {
+    ///
+    /// Applies dotted `key=value` overrides, e.g. `model.dropout=0.2` or `num_epochs=20`.
+    /// Values are parsed as JSON and otherwise taken as strings, so `scaler=Robust` needs no quotes.
+    /// Array elements are addressed by their index, e.g. `model.layers.0=200`.
+    ///
+    pub fn with_overrides<S: AsRef<str>>(self, overrides: &[S]) -> Result<Self, std::io::Error> {
+        let invalid =
+            |message: String| std::io::Error::new(std::io::ErrorKind::InvalidInput, message);
+
+        let mut config = serde_json::to_value(&self).map_err(std::io::Error::other)?;
+        for assignment in overrides {
+            let assignment = assignment.as_ref();
+            let (key, value) = assignment.split_once('=').ok_or_else(|| {
+                invalid(format!(
+                    "Override {assignment} is not of the form key=value"
+                ))
+            })?;
+            let mut target = &mut config;
+            for part in key.split('.') {
+                target = match target {
+                    Value::Object(fields) => fields.get_mut(part),
+                    Value::Array(elements) => part
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| elements.get_mut(index)),
+                    _ => None,
+                }
+                .ok_or_else(|| invalid(format!("Unknown config key {key}")))?;
+            }
+            *target =
+                serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+        }
+        serde_json::from_value(config).map_err(|err| invalid(format!("Invalid override: {err}")))
+    }
+
+    /// Checks that a run started with the `saved` config can be resumed with this one,
+    /// only the [RESUMABLE_FIELDS] may differ.
+    pub fn check_resumable(&self, saved: &TrainingConfig) -> Result<(), std::io::Error> {
+        let fields = |config: &TrainingConfig| match serde_json::to_value(config) {
+            Ok(Value::Object(fields)) => fields,
+            _ => unreachable!("Config is serialized as an object."),
+        };
+        let (fields, saved) = (fields(self), fields(saved));
+        let mut differing = fields
+            .keys()
+            .chain(saved.keys())
+            .filter(|key| !RESUMABLE_FIELDS.contains(&key.as_str()))
+            .filter(|key| fields.get(*key) != saved.get(*key))
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        differing.sort();
+        differing.dedup();
+        if differing.is_empty() {
+            return Ok(());
+        }
+        Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "The fields {} differ from the saved config",
+                differing.join(", ")
+            ),
+        ))
+    }
 }
 
-pub fn train<B: AutodiffBackend>(artifact_dir: &str, config: TrainingConfig, device: B::Device) {
-    create_artifact_dir(artifact_dir);
-    config
-        .save(format!("{artifact_dir}/config.json"))
-        .expect("Config can not be saved.");
+///
+/// Fields of the [TrainingConfig] which may change when a run is resumed, as they only
+/// change how long and how fast the model is trained. A larger `num_epochs` extends a
+/// finished run.
+///
+pub const RESUMABLE_FIELDS: [&str; 3] = ["num_epochs", "num_workers", "feature_cache"];
+
+/// Seed of the train and test split, evaluation has to split the data the same way.
+pub(crate) const SPLIT_SEED: u64 = 42;
+
+/// Written to the artifact directory if there are categorical values outside of the embeddings.
+pub const CATEGORICAL_VIOLATIONS_FILE: &str = "categorical_violations.csv";
+
+/// Sub directory the file checkpointer writes to.
+const CHECKPOINT_DIR: &str = "checkpoint";
+
+/// Outcome of a training run besides the saved model, reported by the caller.
+#[derive(Clone, Debug, Default)]
+pub struct TrainingSummary {
+    /// Epoch of the checkpoint the run was resumed from.
+    pub resumed_from: Option<usize>,
+    /// Categorical values mapped to the unknown bucket in file order,
+    /// all of them are written to the [CATEGORICAL_VIOLATIONS_FILE].
+    pub categorical_violations: Vec<CategoricalViolation>,
+    /// Why the model of an earlier epoch was restored, if training stopped early.
+    pub early_stopping: Option<EarlyStoppingReport>,
+    /// Problems training recovered from, e.g. a feature cache which can not be written.
+    pub warnings: Vec<String>,
+}
+
+/// How an existing artifact directory is treated when training starts.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ArtifactDirMode {
+    /// Refuses to train into a directory which is not empty.
+    #[default]
+    Fresh,
+    /// Deletes the directory with all previous checkpoints.
+    Force,
+    /// Continues from the latest checkpoint, if there is one.
+    /// The config may only differ in the [RESUMABLE_FIELDS].
+    Resume,
+}
+
+/// Epoch of the latest model checkpoint written by the file checkpointer.
+fn latest_checkpoint(artifact_dir: &str) -> Option<usize> {
+    std::fs::read_dir(Path::new(artifact_dir).join(CHECKPOINT_DIR))
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            entry
+                .file_name()
+                .to_str()?
+                .strip_prefix("model-")?
+                .strip_suffix(".mpk")?
+                .parse()
+                .ok()
+        })
+        .max()
+}
+
+fn check_resumed_config(artifact_dir: &str, config: &TrainingConfig) -> Result<(), std::io::Error> {
+    let saved = TrainingConfig::load(format!("{artifact_dir}/config.json"))
+        .map_err(|err| std::io::Error::other(format!("Saved config can not be loaded: {err}")))?;
+    config.check_resumable(&saved).map_err(|err| {
+        std::io::Error::new(err.kind(), format!("{err} of {artifact_dir}/config.json"))
+    })
+}
+
+/// Creates the artifact directory and returns the checkpoint to resume from.
+fn prepare_artifact_dir(
+    artifact_dir: &str,
+    config: &TrainingConfig,
+    mode: ArtifactDirMode,
+) -> Result<Option<usize>, std::io::Error> {
+    let is_empty =
+        std::fs::read_dir(artifact_dir).map_or(true, |mut entries| entries.next().is_none());
+    match mode {
+        ArtifactDirMode::Fresh if !is_empty => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "Artifact directory {artifact_dir} is not empty, resume or force a new run"
+                ),
+            ));
+        }
+        ArtifactDirMode::Force => {
+            std::fs::remove_dir_all(artifact_dir).ok();
+        }
+        ArtifactDirMode::Resume if !is_empty => check_resumed_config(artifact_dir, config)?,
+        _ => {}
+    }
+    std::fs::create_dir_all(artifact_dir)?;
+    match mode {
+        ArtifactDirMode::Resume => match latest_checkpoint(artifact_dir) {
+            Some(epoch) if epoch >= config.num_epochs => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "The run already trained {epoch} epochs, increase num_epochs to continue it"
+                ),
+            )),
+            checkpoint => Ok(checkpoint),
+        },
+        _ => Ok(None),
+    }
+}
+
+/// Writes all violations to the [CATEGORICAL_VIOLATIONS_FILE] of the artifact directory.
+fn save_categorical_violations(
+    artifact_dir: &str,
+    violations: &[CategoricalViolation],
+) -> Result<(), std::io::Error> {
+    if violations.is_empty() {
+        return Ok(());
+    }
+    let mut writer =
+        csv::Writer::from_path(Path::new(artifact_dir).join(CATEGORICAL_VIOLATIONS_FILE))?;
+    for violation in violations {
+        writer.serialize(violation)?;
+    }
+    writer.flush()
+}
+
+///
+/// Trains on the prepared csv file or sqlite database `data_file`.
+/// Invalid inputs, like a config which differs from the one of a resumed run,
+/// are reported as [std::io::ErrorKind::InvalidInput].
+/// Everything else worth reporting is returned in the [TrainingSummary].
+///
+pub fn train<B: AutodiffBackend>(
+    artifact_dir: &str,
+    data_file: &str,
+    config: TrainingConfig,
+    mode: ArtifactDirMode,
+    device: B::Device,
+) -> Result<TrainingSummary, std::io::Error> {
+    let checkpoint = prepare_artifact_dir(artifact_dir, &config, mode)?;
+    config.save(format!("{artifact_dir}/config.json"))?;
 
     B::seed(config.seed);
 
-    let dataset_builder = TaxifareDatasetBuilder::new("TaxiFaresPrepared.csv", 75, Some(42));
-    let test_dataset = dataset_builder.test();
-    let train_dataset = dataset_builder.train();
+    let dataset_builder = TaxifareDatasetBuilder::new(
+        data_file,
+        config.split.clone(),
+        Some(SPLIT_SEED),
+        config.sample_weighting.column(),
+    )?;
+    let scaler = dataset_builder.fit_scaler(config.scaler);
+    scaler.save(artifact_dir)?;
+    let mapper =
+        RawDatafieldToFeaturesMapper::new(scaler, &config.model.categorical_cardinalities())?
+            .with_sample_weights(dataset_builder.sample_weights(config.sample_weighting.clone())?);
+    let categorical_violations = dataset_builder.categorical_violations(&mapper);
+    save_categorical_violations(artifact_dir, &categorical_violations)?;
+    let use_cache = config
+        .feature_cache
+        .unwrap_or_else(|| !is_sqlite_file(data_file));
+    let (features, mut warnings) = dataset_builder.features(&mapper, use_cache)?;
+    let test_dataset = dataset_builder.test(&features);
+    let train_dataset = dataset_builder.train(&features);
 
     let batcher = TaxifareBatcher;
 
@@ -63,22 +315,123 @@ pub fn train<B: AutodiffBackend>(artifact_dir: &str, config: TrainingConfig, dev
         .num_workers(config.num_workers)
         .build(test_dataset);
 
-    let learner = LearnerBuilder::new(artifact_dir)
-        .metric_train_numeric(LossMetric::new())
-        .metric_valid_numeric(LossMetric::new())
-        .with_file_checkpointer(CompactRecorder::new())
-        .devices(vec![device.clone()])
-        .num_epochs(config.num_epochs)
-        .summary()
-        .build(
-            config.model.init::<B>(&device),
-            config.optimizer.init(),
-            config.learning_rate,
-        );
+    let early_stopping = config
+        .early_stopping
+        .clone()
+        .map(|early_stopping| {
+            EarlyStopping::new(early_stopping).persisted(artifact_dir, checkpoint)
+        })
+        .transpose()?;
+    let model_trained = config.optimizer.run::<B, _>(Fit {
+        artifact_dir,
+        config: &config,
+        checkpoint,
+        early_stopping: early_stopping.clone(),
+        dataloader_train,
+        dataloader_test,
+        device: device.clone(),
+    });
 
-    let model_trained = learner.fit(dataloader_train, dataloader_test);
+    warnings.extend(early_stopping.as_ref().and_then(EarlyStopping::save_error));
+    let early_stopping = early_stopping.and_then(|early_stopping| early_stopping.report());
+    let model_trained = match &early_stopping {
+        Some(report) => {
+            report.save(artifact_dir)?;
+            let record = CompactRecorder::new()
+                .load(
+                    format!(
+                        "{artifact_dir}/{CHECKPOINT_DIR}/model-{}",
+                        report.best_epoch
+                    )
+                    .into(),
+                    &device,
+                )
+                .map_err(|err| {
+                    std::io::Error::other(format!("Best checkpoint can not be loaded: {err}"))
+                })?;
+            model_trained.load_record(record)
+        }
+        None => model_trained,
+    };
 
     model_trained
         .save_file(format!("{artifact_dir}/model"), &CompactRecorder::new())
-        .expect("Trained model can not be saved.");
+        .map_err(|err| std::io::Error::other(format!("Trained model can not be saved: {err}")))?;
+    Ok(TrainingSummary {
+        resumed_from: checkpoint,
+        categorical_violations,
+        early_stopping,
+        warnings,
+    })
+}
+
+/// Runs the learner with the optimizer of the config.
+struct Fit<'a, B: AutodiffBackend> {
+    artifact_dir: &'a str,
+    config: &'a TrainingConfig,
+    checkpoint: Option<usize>,
+    early_stopping: Option<EarlyStopping>,
+    dataloader_train: Arc<dyn DataLoader<B, TaxifareBatch<B>>>,
+    dataloader_test: Arc<dyn DataLoader<B::InnerBackend, TaxifareBatch<B::InnerBackend>>>,
+    device: B::Device,
+}
+
+impl<B: AutodiffBackend> OptimizerTask<B> for Fit<'_, B> {
+    type Output = Model<B>;
+
+    fn run<O>(self, optimizer: O) -> Model<B>
+    where
+        O: Optimizer<Model<B>, B>,
+        O::Record: 'static,
+    {
+        let Fit {
+            artifact_dir,
+            config,
+            checkpoint,
+            early_stopping,
+            dataloader_train,
+            dataloader_test,
+            device,
+        } = self;
+
+        let mut learner_builder = LearnerBuilder::new(artifact_dir)
+            .metric_train_numeric(LossMetric::new())
+            .metric_valid_numeric(LossMetric::new())
+            .metric_train_numeric(RegressionMetric::rmse())
+            .metric_valid_numeric(RegressionMetric::rmse())
+            .metric_train_numeric(RegressionMetric::mae())
+            .metric_valid_numeric(RegressionMetric::mae())
+            .metric_train_numeric(RegressionMetric::r2())
+            .metric_valid_numeric(RegressionMetric::r2())
+            .metric_train_numeric(RegressionMetric::mape())
+            .metric_valid_numeric(RegressionMetric::mape())
+            .metric_train_numeric(RegressionMetric::median_absolute_error())
+            .metric_valid_numeric(RegressionMetric::median_absolute_error())
+            .metric_train_numeric(LearningRateMetric::new())
+            .with_file_checkpointer(CompactRecorder::new())
+            .devices(vec![device.clone()])
+            .num_epochs(config.num_epochs)
+            .summary();
+        if let Some(epoch) = checkpoint {
+            learner_builder = learner_builder.checkpoint(epoch);
+        }
+        if let Some(early_stopping) = early_stopping {
+            // The last checkpoints are kept for resuming, the best one for restoring it
+            learner_builder = learner_builder
+                .with_checkpointing_strategy(
+                    ComposedCheckpointingStrategy::builder()
+                        .add(KeepLastNCheckpoints::new(2))
+                        .add(early_stopping.checkpointing())
+                        .build(),
+                )
+                .early_stopping(early_stopping);
+        }
+        let learner = learner_builder.build(
+            config.model.init::<B>(&device),
+            optimizer,
+            config.lr_schedule.init(config.learning_rate),
+        );
+
+        learner.fit(dataloader_train, dataloader_test)
+    }
 }
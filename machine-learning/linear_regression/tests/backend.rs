@@ -0,0 +1,71 @@
+use std::io::ErrorKind;
+
+use burn::tensor::backend::AutodiffBackend;
+use linear_regression::backend::{BackendKind, BackendTask};
+
+/// Returns the name of the backend it runs on.
+struct BackendName;
+
+impl BackendTask for BackendName {
+    type Output = String;
+
+    fn run<B: AutodiffBackend>(self, device: B::Device) -> String {
+        B::name(&device)
+    }
+}
+
+#[test]
+fn names_are_parsed_case_insensitive() {
+    for (name, kind) in [
+        ("ndarray", BackendKind::NdArray),
+        ("NdArray", BackendKind::NdArray),
+        ("candle", BackendKind::Candle),
+        ("tch", BackendKind::LibTorch),
+        ("libtorch", BackendKind::LibTorch),
+        ("LibTorch", BackendKind::LibTorch),
+        ("WGPU", BackendKind::Wgpu),
+        ("metal", BackendKind::Metal),
+    ] {
+        assert_eq!(name.parse::<BackendKind>().unwrap(), kind, "{name}");
+    }
+}
+
+#[test]
+fn unknown_names_are_rejected() {
+    for name in ["", "cuda", "nd-array"] {
+        let err = name.parse::<BackendKind>().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{name}");
+    }
+}
+
+#[test]
+fn display_names_parse_back() {
+    for kind in BackendKind::ALL {
+        assert_eq!(kind.to_string().parse::<BackendKind>().unwrap(), kind);
+        assert_eq!(kind.to_string(), kind.feature());
+    }
+}
+
+#[test]
+fn default_backend_is_compiled_in() {
+    assert!(BackendKind::default().is_compiled());
+    assert!(BackendKind::compiled().contains(&BackendKind::NdArray));
+    assert!(!BackendKind::default().run(BackendName).unwrap().is_empty());
+}
+
+#[test]
+fn backends_which_are_not_compiled_in_are_errors() {
+    let compiled = BackendKind::compiled();
+    for kind in BackendKind::ALL
+        .into_iter()
+        .filter(|kind| !kind.is_compiled())
+    {
+        let err = kind.run(BackendName).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::Unsupported, "{kind}");
+        let message = err.to_string();
+        assert!(message.contains(kind.feature()), "{message}");
+        for compiled in &compiled {
+            assert!(message.contains(&compiled.to_string()), "{message}");
+        }
+    }
+}
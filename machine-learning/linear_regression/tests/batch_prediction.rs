@@ -0,0 +1,189 @@
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+use burn::{
+    backend::{NdArray, ndarray::NdArrayDevice},
+    config::Config,
+    module::Module,
+    record::CompactRecorder,
+};
+use linear_regression::{
+    batch_prediction::{BatchPredictionOptions, BatchPredictionSummary, predict_file},
+    dataset::scaler::FeatureScaler,
+    models::taxifare_model::ModelConfig,
+    training::TrainingConfig,
+};
+use polars::prelude::*;
+
+type B = NdArray;
+
+const TRIPS: usize = 10;
+
+/// Directory with an untrained model, enough to check the rows of the output.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "taxifare-batch-prediction-{}-{name}",
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("Test directory can not be created.");
+
+    let config = TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[16, 8],
+        0.4,
+    ));
+    config
+        .save(dir.join("config.json"))
+        .expect("Config can not be saved.");
+    config
+        .model
+        .init::<B>(&NdArrayDevice::Cpu)
+        .save_file(dir.join("model"), &CompactRecorder::new())
+        .expect("Model can not be saved.");
+    FeatureScaler::default()
+        .save(dir.to_str().unwrap())
+        .expect("Scaler can not be saved.");
+    dir
+}
+
+fn predict(dir: &Path, input: &str, output: &str) -> BatchPredictionSummary {
+    predict_file::<B>(
+        dir.to_str().unwrap(),
+        dir.join(input).to_str().unwrap(),
+        dir.join(output).to_str().unwrap(),
+        BatchPredictionOptions {
+            chunk_size: 3,
+            with_errors: true,
+        },
+        NdArrayDevice::Cpu,
+    )
+    .expect("Prediction should succeed.")
+}
+
+#[test]
+fn csv_rows_keep_their_order() {
+    let dir = test_dir("csv");
+    let mut input = String::from(
+        "key,fare_amount,pickup_datetime,pickup_longitude,pickup_latitude,\
+dropoff_longitude,dropoff_latitude,passenger_count\n",
+    );
+    for key in 0..TRIPS {
+        // The trip with key 4 has an invalid pickup time
+        let datetime = if key == 4 {
+            "yesterday".to_string()
+        } else {
+            format!("2013-07-0{} 1{key}:30:00 UTC", key % 7 + 1)
+        };
+        input.push_str(&format!(
+            "{key},{}.5,{datetime},-73.98,40.75,-73.9{key},40.7{key},{}\n",
+            key + 3,
+            key % 4 + 1
+        ));
+    }
+    std::fs::write(dir.join("trips.csv"), input).unwrap();
+
+    let summary = predict(&dir, "trips.csv", "predictions.csv");
+
+    assert_eq!((summary.rows, summary.predicted), (TRIPS, TRIPS - 1));
+    let mut reader = csv::Reader::from_path(dir.join("predictions.csv")).unwrap();
+    let headers = reader.headers().unwrap().clone();
+    let column = |name| headers.iter().position(|header| header == name).unwrap();
+    let (key, fare, predicted, residual) = (
+        column("key"),
+        column("fare_amount"),
+        column("predicted_fare"),
+        column("residual"),
+    );
+    let records = reader.records().map(Result::unwrap).collect::<Vec<_>>();
+    assert_eq!(records.len(), TRIPS);
+    for (index, record) in records.iter().enumerate() {
+        assert_eq!(record[key], index.to_string());
+        if index == 4 {
+            assert_eq!(&record[predicted], "");
+            assert_eq!(&record[residual], "");
+            continue;
+        }
+        let fare: f64 = record[fare].parse().unwrap();
+        let predicted: f64 = record[predicted].parse().unwrap();
+        let residual: f64 = record[residual].parse().unwrap();
+        assert!((fare - predicted - residual).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn parquet_rows_keep_their_order_across_row_groups() {
+    let dir = test_dir("parquet");
+    let keys = (0..TRIPS as i64).collect::<Vec<_>>();
+    let float = |f: fn(i64) -> f64| keys.iter().map(|key| f(*key)).collect::<Vec<_>>();
+    let category = |f: fn(i64) -> Option<i64>| keys.iter().map(|key| f(*key)).collect::<Vec<_>>();
+    let mut df = DataFrame::new(vec![
+        Column::new("key".into(), &keys),
+        Column::new("fare_amount".into(), float(|key| key as f64 + 3.5)),
+        Column::new("pickup_latitude".into(), float(|_| 40.75)),
+        Column::new("pickup_longitude".into(), float(|_| -73.98)),
+        Column::new(
+            "dropoff_latitude".into(),
+            float(|key| 40.7 + key as f64 / 100.0),
+        ),
+        Column::new(
+            "dropoff_longitude".into(),
+            float(|key| -73.9 - key as f64 / 100.0),
+        ),
+        Column::new("passenger_count".into(), float(|key| (key % 4 + 1) as f64)),
+        Column::new("distance".into(), float(|key| 1.0 + key as f64)),
+        // The trip with key 4 has no pickup hour
+        Column::new(
+            "pickup_hour".into(),
+            category(|key| (key != 4).then_some(key + 8)),
+        ),
+        Column::new("pickup_weekday".into(), category(|key| Some(key % 7))),
+        Column::new("am_or_pm".into(), category(|key| Some((key + 8) / 12))),
+        Column::new(
+            "pickup_timestamp".into(),
+            keys.iter()
+                .map(|key| 1_372_680_000_000 + key * 3_600_000)
+                .collect::<Vec<_>>(),
+        )
+        .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))
+        .unwrap(),
+    ])
+    .unwrap();
+    ParquetWriter::new(File::create(dir.join("trips.parquet")).unwrap())
+        .with_row_group_size(Some(4))
+        .finish(&mut df)
+        .unwrap();
+
+    let summary = predict(&dir, "trips.parquet", "predictions.parquet");
+
+    assert_eq!((summary.rows, summary.predicted), (TRIPS, TRIPS - 1));
+    let output = ParquetReader::new(File::open(dir.join("predictions.parquet")).unwrap())
+        .finish()
+        .unwrap();
+    assert_eq!(output.height(), TRIPS);
+    let output_keys = output
+        .column("key")
+        .unwrap()
+        .i64()
+        .unwrap()
+        .into_no_null_iter()
+        .collect::<Vec<_>>();
+    assert_eq!(output_keys, keys);
+    let predicted = output
+        .column("predicted_fare")
+        .unwrap()
+        .f32()
+        .unwrap()
+        .into_iter()
+        .collect::<Vec<_>>();
+    for (key, fare) in predicted.iter().enumerate() {
+        assert_eq!(fare.is_none(), key == 4, "predicted fare of key {key}");
+    }
+    assert_eq!(
+        output.column("pickup_timestamp").unwrap().dtype(),
+        &DataType::Datetime(TimeUnit::Milliseconds, None)
+    );
+}
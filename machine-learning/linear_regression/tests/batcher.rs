@@ -0,0 +1,153 @@
+use burn::{backend::NdArray, data::dataloader::batcher::Batcher, prelude::*};
+use linear_regression::{
+    batcher::{TaxifareBatch, TaxifareBatcher, TaxifareInferenceBatch, TaxifareInferenceBatcher},
+    dataset::mapped_dataset::{TaxifareDatasetFeaturesItem, TaxifareDatasetMappedItem},
+    models::taxifare_model::ModelConfig,
+};
+
+type B = NdArray;
+
+fn item(index: usize, weight: Option<f64>) -> TaxifareDatasetMappedItem {
+    let value = index as f64;
+    TaxifareDatasetMappedItem {
+        discrete_weekday: (index % 7) as u8,
+        discrete_hour: (index % 24) as u8,
+        discrete_am_or_pm: (index % 2) as u8,
+        continuous_features: [
+            value,
+            value + 0.1,
+            value + 0.2,
+            value + 0.3,
+            value + 0.4,
+            value + 0.5,
+        ],
+        continuous_mask: [true; 6],
+        label: 10.0 * value,
+        weight,
+    }
+}
+
+fn batch(items: Vec<TaxifareDatasetMappedItem>) -> TaxifareBatch<B> {
+    TaxifareBatcher.batch(items, &Default::default())
+}
+
+#[test]
+fn batch_has_one_row_per_item() {
+    let batch = batch((0..5).map(|index| item(index, None)).collect());
+
+    assert_eq!(batch.cont_features.dims(), [5, 6]);
+    assert_eq!(batch.cont_mask.dims(), [5, 6]);
+    assert_eq!(batch.cat_features.len(), 3);
+    for cat_feature in &batch.cat_features {
+        assert_eq!(cat_feature.dims(), [5, 1]);
+    }
+    assert_eq!(batch.predictions.dims(), [5, 1]);
+    assert!(batch.weights.is_none());
+}
+
+#[test]
+fn batch_keeps_item_order() {
+    let items = (0..30).map(|index| item(index, None)).collect::<Vec<_>>();
+    let batch = batch(items.clone());
+
+    let cont_features = batch.cont_features.into_data().to_vec::<f32>().unwrap();
+    let expected = items
+        .iter()
+        .flat_map(|item| item.continuous_features)
+        .map(|value| value as f32)
+        .collect::<Vec<_>>();
+    assert_eq!(cont_features, expected);
+
+    let hours = batch.cat_features[1]
+        .clone()
+        .into_data()
+        .to_vec::<i64>()
+        .unwrap();
+    let expected = items
+        .iter()
+        .map(|item| item.discrete_hour as i64)
+        .collect::<Vec<_>>();
+    assert_eq!(hours, expected);
+
+    let labels = batch.predictions.into_data().to_vec::<f32>().unwrap();
+    let expected = items
+        .iter()
+        .map(|item| item.label as f32)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, expected);
+}
+
+#[test]
+fn batch_of_weighted_items_has_weights() {
+    let batch = batch(
+        (0..4)
+            .map(|index| item(index, Some(0.5 * index as f64)))
+            .collect(),
+    );
+
+    let weights = batch.weights.expect("Weighted items have weights");
+    assert_eq!(weights.dims(), [4, 1]);
+    assert_eq!(
+        weights.into_data().to_vec::<f32>().unwrap(),
+        vec![0.0, 0.5, 1.0, 1.5]
+    );
+}
+
+#[test]
+fn model_output_has_one_prediction_per_item() {
+    let device = Default::default();
+    let model =
+        ModelConfig::new(vec![(7, 4), (24, 12), (2, 1)], 6, &[100, 50], 0.4).init::<B>(&device);
+    let batch = batch((0..8).map(|index| item(index, None)).collect());
+
+    let output = model.forward(batch.cat_features, batch.cont_features, batch.cont_mask);
+
+    assert_eq!(output.dims(), [8, 1]);
+}
+
+#[test]
+fn unlabeled_batch_matches_labeled_features() {
+    let items = (0..6).map(|index| item(index, None)).collect::<Vec<_>>();
+    let unlabeled = items
+        .iter()
+        .map(|item| TaxifareDatasetFeaturesItem {
+            discrete_weekday: item.discrete_weekday,
+            discrete_hour: item.discrete_hour,
+            discrete_am_or_pm: item.discrete_am_or_pm,
+            continuous_features: item.continuous_features,
+            continuous_mask: item.continuous_mask,
+        })
+        .collect();
+
+    let labeled = batch(items);
+    let unlabeled: TaxifareInferenceBatch<B> =
+        TaxifareInferenceBatcher.batch(unlabeled, &Default::default());
+
+    unlabeled
+        .cont_features
+        .into_data()
+        .assert_eq(&labeled.cont_features.into_data(), true);
+    assert_eq!(unlabeled.cat_features.len(), 3);
+    for (cat_feature, expected) in unlabeled.cat_features.into_iter().zip(labeled.cat_features) {
+        assert_eq!(cat_feature.dims(), [6, 1]);
+        cat_feature
+            .into_data()
+            .assert_eq(&expected.into_data(), true);
+    }
+}
+
+#[test]
+fn missing_features_are_masked() {
+    let mut partial = item(1, None);
+    partial.continuous_features[4] = 0.0;
+    partial.continuous_mask[4] = false;
+    let batch = batch(vec![item(0, None), partial]);
+
+    assert_eq!(
+        batch.cont_mask.into_data().to_vec::<f32>().unwrap(),
+        vec![
+            1.0, 1.0, 1.0, 1.0, 1.0, 1.0, //
+            1.0, 1.0, 1.0, 1.0, 0.0, 1.0,
+        ]
+    );
+}
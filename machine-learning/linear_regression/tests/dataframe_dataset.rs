@@ -0,0 +1,117 @@
+use burn::data::dataset::Dataset;
+use dataframe_dataset::DataFrameDataset;
+use linear_regression::dataset::raw_dataset::TaxifareDatasetRawItem;
+use polars::prelude::*;
+
+/// Three trips built in code, the second one without passenger count and distance.
+fn trips(optional_columns: bool) -> DataFrame {
+    let mut columns = vec![
+        Column::new("fare_amount".into(), [4.5, 16.9, 5.7]),
+        Column::new("pickup_latitude".into(), [40.73, 40.76, 40.75]),
+        Column::new("pickup_longitude".into(), [-73.99, -73.97, -73.98]),
+        Column::new("dropoff_latitude".into(), [40.72, 40.74, 40.76]),
+        Column::new("dropoff_longitude".into(), [-73.98, -73.99, -73.99]),
+        Column::new("passenger_count".into(), [Some(1.0), None, Some(2.0)]),
+        Column::new("distance".into(), [Some(1.03), None, Some(0.76)]),
+        Column::new("pickup_hour".into(), [4i64, 16, 11]),
+        Column::new("pickup_weekday".into(), [1i64, 4, 0]),
+        Column::new("am_or_pm".into(), [0i64, 1, 0]),
+    ];
+    if optional_columns {
+        columns.push(Column::new(
+            "pickup_timestamp".into(),
+            [Some(1_366_000_000i64), None, Some(1_366_100_000)],
+        ));
+        columns.push(Column::new(
+            "sample_weight".into(),
+            [None, Some(2.0), Some(0.5)],
+        ));
+    }
+    DataFrame::new(columns).unwrap()
+}
+
+fn items(df: &DataFrame) -> Vec<Option<TaxifareDatasetRawItem>> {
+    let dataset = DataFrameDataset::<TaxifareDatasetRawItem>::new(df).unwrap();
+    (0..dataset.len()).map(|index| dataset.get(index)).collect()
+}
+
+#[test]
+fn rows_are_read_as_items() {
+    let items = items(&trips(true));
+
+    assert_eq!(items.len(), 3);
+    let first = items[0].as_ref().unwrap();
+    assert_eq!(first.fare_amount, 4.5);
+    assert_eq!(
+        (first.pickup_latitude, first.pickup_longitude),
+        (40.73, -73.99)
+    );
+    assert_eq!(
+        (first.dropoff_latitude, first.dropoff_longitude),
+        (40.72, -73.98)
+    );
+    assert_eq!(
+        (first.pickup_hour, first.pickup_weekday, first.am_or_pm),
+        (4, 1, 0)
+    );
+    assert_eq!(first.pickup_timestamp, Some(1_366_000_000));
+}
+
+#[test]
+fn nulls_are_missing_values() {
+    let items = items(&trips(true))
+        .into_iter()
+        .map(Option::unwrap)
+        .collect::<Vec<_>>();
+
+    let passenger_counts = items
+        .iter()
+        .map(|item| item.passenger_count)
+        .collect::<Vec<_>>();
+    assert_eq!(passenger_counts, [Some(1.0), None, Some(2.0)]);
+    let distances = items.iter().map(|item| item.distance).collect::<Vec<_>>();
+    assert_eq!(distances, [Some(1.03), None, Some(0.76)]);
+    let timestamps = items
+        .iter()
+        .map(|item| item.pickup_timestamp)
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps, [Some(1_366_000_000), None, Some(1_366_100_000)]);
+    let weights = items
+        .iter()
+        .map(|item| item.sample_weight)
+        .collect::<Vec<_>>();
+    assert_eq!(weights, [None, Some(2.0), Some(0.5)]);
+}
+
+#[test]
+fn optional_columns_may_be_absent() {
+    let items = items(&trips(false));
+
+    assert!(items.iter().all(|item| {
+        let item = item.as_ref().unwrap();
+        item.pickup_timestamp.is_none() && item.sample_weight.is_none()
+    }));
+}
+
+#[test]
+fn rows_with_a_null_required_value_are_skipped() {
+    let mut df = trips(false);
+    df.replace(
+        "fare_amount",
+        Series::new("fare_amount".into(), [Some(4.5), None, Some(5.7)]),
+    )
+    .unwrap();
+
+    let items = items(&df);
+
+    assert!(items[0].is_some());
+    assert!(items[1].is_none());
+    assert!(items[2].is_some());
+}
+
+#[test]
+fn required_columns_have_to_exist() {
+    let df = trips(false).drop("distance").unwrap();
+
+    assert!(DataFrameDataset::<TaxifareDatasetRawItem>::new(&df).is_err());
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+
+use linear_regression::early_stopping::{
+    EarlyStopping, EarlyStoppingConfig, MetricDirection, ValidationMetrics,
+};
+
+/// Validation metric values by epoch, in place of the event store of the learner.
+struct FakeEventStore(HashMap<usize, f64>);
+
+impl FakeEventStore {
+    fn new(values: &[f64]) -> Self {
+        Self(
+            values
+                .iter()
+                .enumerate()
+                .map(|(index, value)| (index + 1, *value))
+                .collect(),
+        )
+    }
+}
+
+impl ValidationMetrics for FakeEventStore {
+    fn valid_metric(&self, name: &str, epoch: usize) -> Option<f64> {
+        assert_eq!(name, "RMSE");
+        self.0.get(&epoch).copied()
+    }
+}
+
+fn early_stopping(direction: MetricDirection, patience: usize, min_delta: f64) -> EarlyStopping {
+    EarlyStopping::new(EarlyStoppingConfig {
+        metric: "RMSE".to_string(),
+        direction,
+        patience,
+        min_delta,
+    })
+}
+
+/// Epochs after which training is stopped.
+fn stops(early_stopping: &EarlyStopping, store: &FakeEventStore, epochs: usize) -> Vec<bool> {
+    (1..=epochs)
+        .map(|epoch| early_stopping.should_stop_after(epoch, store))
+        .collect()
+}
+
+#[test]
+fn stops_after_patience_epochs_without_improvement() {
+    let early_stopping = early_stopping(MetricDirection::Lowest, 2, 0.0);
+    let store = FakeEventStore::new(&[5.0, 4.0, 4.5, 4.2, 3.0]);
+
+    assert_eq!(
+        stops(&early_stopping, &store, 4),
+        [false, false, false, true]
+    );
+    let report = early_stopping.report().unwrap();
+    assert_eq!((report.best_epoch, report.best_value), (2, 4.0));
+    assert_eq!(report.last_epoch, 4);
+}
+
+#[test]
+fn improvements_smaller_than_min_delta_are_ignored() {
+    let early_stopping = early_stopping(MetricDirection::Lowest, 2, 0.5);
+    let store = FakeEventStore::new(&[5.0, 4.7, 4.4, 4.0, 3.95]);
+
+    assert_eq!(
+        stops(&early_stopping, &store, 5),
+        [false, false, false, false, true]
+    );
+    let report = early_stopping.report().unwrap();
+    assert_eq!((report.best_epoch, report.best_value), (3, 4.4));
+}
+
+#[test]
+fn direction_decides_which_values_are_better() {
+    let store = FakeEventStore::new(&[0.5, 0.6, 0.55]);
+
+    let highest = early_stopping(MetricDirection::Highest, 5, 0.0);
+    stops(&highest, &store, 3);
+    assert_eq!(highest.report().unwrap().best_epoch, 2);
+
+    let lowest = early_stopping(MetricDirection::Lowest, 5, 0.0);
+    stops(&lowest, &store, 3);
+    assert_eq!(lowest.report().unwrap().best_epoch, 1);
+}
+
+#[test]
+fn epochs_are_only_recorded_once() {
+    let early_stopping = early_stopping(MetricDirection::Lowest, 1, 0.0);
+    let store = FakeEventStore::new(&[5.0, 6.0]);
+
+    assert!(!early_stopping.should_stop_after(1, &store));
+    assert!(early_stopping.should_stop_after(2, &store));
+    assert!(early_stopping.should_stop_after(2, &store));
+    assert_eq!(early_stopping.report().unwrap().best_epoch, 1);
+}
+
+#[test]
+fn missing_metric_never_stops() {
+    let early_stopping = early_stopping(MetricDirection::Lowest, 1, 0.0);
+    let store = FakeEventStore::new(&[]);
+
+    assert_eq!(stops(&early_stopping, &store, 3), [false, false, false]);
+    assert!(early_stopping.report().is_none());
+}
+
+#[test]
+fn resume_restores_the_state_up_to_the_checkpoint() {
+    let dir = std::env::temp_dir().join(format!("taxifare-early-stopping-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let artifact_dir = dir.to_str().unwrap();
+
+    let first_run = early_stopping(MetricDirection::Lowest, 3, 0.0)
+        .persisted(artifact_dir, None)
+        .unwrap();
+    stops(&first_run, &FakeEventStore::new(&[5.0, 3.0, 4.0, 2.0]), 4);
+
+    // Epoch 4 was not checkpointed and is trained again with a different result
+    let resumed = early_stopping(MetricDirection::Lowest, 3, 0.0)
+        .persisted(artifact_dir, Some(3))
+        .unwrap();
+    let store = FakeEventStore::new(&[5.0, 3.0, 4.0, 4.5, 4.2]);
+    assert!(!resumed.should_stop_after(4, &store));
+    assert!(resumed.should_stop_after(5, &store));
+    let report = resumed.report().unwrap();
+    assert_eq!((report.best_epoch, report.best_value), (2, 3.0));
+    assert_eq!(report.last_epoch, 5);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn training_continues_if_the_state_can_not_be_saved() {
+    let dir = std::env::temp_dir().join(format!(
+        "taxifare-early-stopping-unwritable-{}",
+        std::process::id()
+    ));
+    // A directory in place of the state file can not be written
+    std::fs::create_dir_all(dir.join("early_stopping_state.json")).unwrap();
+
+    let early_stopping = early_stopping(MetricDirection::Lowest, 1, 0.0)
+        .persisted(dir.to_str().unwrap(), None)
+        .unwrap();
+    let store = FakeEventStore::new(&[5.0, 6.0]);
+
+    assert_eq!(stops(&early_stopping, &store, 2), [false, true]);
+    let error = early_stopping.save_error().unwrap();
+    assert!(error.contains("epoch 2"), "{error}");
+    assert_eq!(early_stopping.report().unwrap().best_epoch, 1);
+}
@@ -0,0 +1,167 @@
+use std::{io::ErrorKind, path::PathBuf};
+
+use burn::{
+    backend::{NdArray, ndarray::NdArrayDevice},
+    config::Config,
+    module::Module,
+    record::CompactRecorder,
+};
+use linear_regression::{
+    dataset::scaler::FeatureScaler,
+    evaluation::{EvaluationReport, EvaluationSplit, evaluate},
+    models::taxifare_model::ModelConfig,
+    training::TrainingConfig,
+};
+
+type B = NdArray;
+
+const TRIPS: usize = 20;
+
+const HEADER: &str = "fare_amount,pickup_latitude,pickup_longitude,dropoff_latitude,dropoff_longitude,passenger_count,distance,pickup_hour,pickup_weekday,am_or_pm";
+
+/// Directory with an untrained model and a prepared file of [TRIPS] trips,
+/// every fifth trip has no distance.
+fn test_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("taxifare-evaluation-{}-{name}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("Test directory can not be created.");
+
+    let config = TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[16, 8],
+        0.4,
+    ));
+    config
+        .save(dir.join("config.json"))
+        .expect("Config can not be saved.");
+    config
+        .model
+        .init::<B>(&NdArrayDevice::Cpu)
+        .save_file(dir.join("model"), &CompactRecorder::new())
+        .expect("Model can not be saved.");
+    FeatureScaler::default()
+        .save(dir.to_str().unwrap())
+        .expect("Scaler can not be saved.");
+
+    let rows = (0..TRIPS)
+        .map(|trip| {
+            let distance = if trip % 5 == 0 {
+                String::new()
+            } else {
+                format!("{}.5", trip % 8)
+            };
+            format!(
+                "{}.5,40.75,-73.98,40.7,-73.9,1,{distance},{},{},{}",
+                trip + 3,
+                trip % 3 + 10,
+                trip % 7,
+                trip % 2
+            )
+        })
+        .collect::<Vec<_>>();
+    std::fs::write(
+        dir.join("trips.csv"),
+        format!("{HEADER}\n{}\n", rows.join("\n")),
+    )
+    .unwrap();
+    dir
+}
+
+fn run(dir: &std::path::Path, split: EvaluationSplit) -> Result<EvaluationReport, std::io::Error> {
+    evaluate::<B>(
+        dir.to_str().unwrap(),
+        dir.join("trips.csv").to_str().unwrap(),
+        split,
+        NdArrayDevice::Cpu,
+    )
+}
+
+/// Number of items over the groups of one column.
+fn group_count(report: &EvaluationReport, group: &str) -> usize {
+    report
+        .groups
+        .iter()
+        .filter(|scores| scores.group == group)
+        .map(|scores| scores.count)
+        .sum()
+}
+
+#[test]
+fn every_group_column_covers_all_items() {
+    let dir = test_dir("groups");
+
+    let report = run(&dir, EvaluationSplit::All).unwrap();
+
+    assert_eq!(report.overall.count, TRIPS);
+    assert!(report.overall.rmse.is_finite());
+    for group in [
+        "pickup_hour",
+        "pickup_weekday",
+        "am_or_pm",
+        "distance_bucket",
+        "fare_bucket",
+    ] {
+        assert_eq!(group_count(&report, group), TRIPS, "{group}");
+    }
+    let hours = report
+        .groups
+        .iter()
+        .filter(|scores| scores.group == "pickup_hour")
+        .map(|scores| (scores.value.as_str(), scores.count))
+        .collect::<Vec<_>>();
+    assert_eq!(hours, [("10", 7), ("11", 7), ("12", 6)]);
+    let missing_distances = report
+        .groups
+        .iter()
+        .find(|scores| scores.group == "distance_bucket" && scores.value == "missing")
+        .unwrap();
+    assert_eq!(missing_distances.count, TRIPS / 5);
+}
+
+#[test]
+fn train_and_test_splits_partition_the_items() {
+    let dir = test_dir("splits");
+
+    let train = run(&dir, EvaluationSplit::Train).unwrap();
+    let test = run(&dir, EvaluationSplit::Test).unwrap();
+
+    assert_eq!(train.overall.count, TRIPS * 75 / 100);
+    assert_eq!(train.overall.count + test.overall.count, TRIPS);
+}
+
+#[test]
+fn report_is_saved_as_json_and_csv() {
+    let dir = test_dir("save");
+
+    let report = run(&dir, EvaluationSplit::Test).unwrap();
+    report.save(dir.to_str().unwrap()).unwrap();
+
+    let json = std::fs::read_to_string(dir.join("evaluation_test.json")).unwrap();
+    let saved: EvaluationReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(saved.overall.count, report.overall.count);
+    let csv = std::fs::read_to_string(dir.join("evaluation_test.csv")).unwrap();
+    // Header, overall and one row per group
+    assert_eq!(csv.lines().count(), report.groups.len() + 2);
+    assert!(csv.lines().nth(1).unwrap().starts_with("overall,all,"));
+}
+
+#[test]
+fn missing_inputs_are_errors() {
+    let dir = test_dir("missing");
+
+    let err = evaluate::<B>(
+        dir.join("no-model").to_str().unwrap(),
+        dir.join("trips.csv").to_str().unwrap(),
+        EvaluationSplit::All,
+        NdArrayDevice::Cpu,
+    )
+    .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+
+    std::fs::remove_file(dir.join("trips.csv")).unwrap();
+    for split in [EvaluationSplit::All, EvaluationSplit::Test] {
+        assert!(run(&dir, split).is_err(), "{split}");
+    }
+}
@@ -0,0 +1,263 @@
+use std::path::{Path, PathBuf};
+
+use burn::data::dataset::{Dataset, InMemDataset};
+use linear_regression::dataset::{
+    feature_cache::{CacheKey, FeatureCache},
+    mapped_dataset::{RawDatafieldToFeaturesMapper, TaxifareDatasetMappedItem},
+    scaler::FeatureScaler,
+    taxifare_dataset::{SplitConfig, TaxifareDatasetBuilder},
+};
+
+/// Empty directory which is unique to the test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "taxifare-feature-cache-{}-{name}",
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("Test directory can not be created.");
+    dir
+}
+
+fn item(index: usize, label: f64) -> TaxifareDatasetMappedItem {
+    let value = index as f64;
+    TaxifareDatasetMappedItem {
+        discrete_weekday: (index % 7) as u8,
+        discrete_hour: (index % 24) as u8,
+        discrete_am_or_pm: (index % 2) as u8,
+        continuous_features: [value, value + 0.1, value + 0.2, value + 0.3, 0.0, value],
+        continuous_mask: [true, true, true, true, false, true],
+        label,
+        weight: Some(1.0 + value),
+    }
+}
+
+fn dataset(labels: &[f64]) -> InMemDataset<TaxifareDatasetMappedItem> {
+    InMemDataset::new(
+        labels
+            .iter()
+            .enumerate()
+            .map(|(index, label)| item(index, *label))
+            .collect(),
+    )
+}
+
+fn labels(dataset: &impl Dataset<TaxifareDatasetMappedItem>) -> Vec<f64> {
+    dataset.iter().map(|item| item.label).collect()
+}
+
+#[test]
+fn round_trip_keeps_all_items() {
+    let dir = test_dir("round-trip");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default().update(b"source");
+    let source = dataset(&[3.5, 7.0, 12.25]);
+
+    FeatureCache::from_dataset(&source)
+        .save(&cache_file, key)
+        .unwrap();
+    let cache = FeatureCache::load(&cache_file, key).unwrap().unwrap();
+
+    assert_eq!(cache.len(), source.len());
+    for (cached, item) in cache.iter().zip(source.iter()) {
+        assert_eq!(format!("{cached:?}"), format!("{item:?}"));
+    }
+    // Only the cache file is left, the temporary file was renamed
+    let files = std::fs::read_dir(&dir).unwrap().count();
+    assert_eq!(files, 1);
+}
+
+#[test]
+fn items_without_a_weight_keep_their_row_when_others_are_weighted() {
+    let dir = test_dir("partial-weights");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default();
+    let mut items = (0..3)
+        .map(|index| item(index, index as f64))
+        .collect::<Vec<_>>();
+    items[0].weight = None;
+    items[2].weight = Some(4.0);
+
+    FeatureCache::from_dataset(&InMemDataset::new(items))
+        .save(&cache_file, key)
+        .unwrap();
+    let cache = FeatureCache::load(&cache_file, key).unwrap().unwrap();
+
+    let weights = cache.iter().map(|item| item.weight).collect::<Vec<_>>();
+    assert_eq!(weights, [Some(1.0), Some(2.0), Some(4.0)]);
+}
+
+#[test]
+fn unweighted_items_are_cached_without_weights() {
+    let dir = test_dir("unweighted");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default();
+    let items = (0..2)
+        .map(|index| TaxifareDatasetMappedItem {
+            weight: None,
+            ..item(index, 1.0)
+        })
+        .collect();
+
+    FeatureCache::from_dataset(&InMemDataset::new(items))
+        .save(&cache_file, key)
+        .unwrap();
+    let cache = FeatureCache::load(&cache_file, key).unwrap().unwrap();
+
+    assert!(cache.iter().all(|item| item.weight.is_none()));
+}
+
+#[test]
+fn cache_with_the_same_key_is_reused() {
+    let dir = test_dir("reused");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default().update(b"source");
+
+    FeatureCache::load_or_create(&cache_file, key, &dataset(&[1.0, 2.0]));
+    let (cache, warnings) = FeatureCache::load_or_create(&cache_file, key, &dataset(&[5.0]));
+
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(labels(&cache), [1.0, 2.0]);
+}
+
+#[test]
+fn cache_which_can_not_be_written_is_kept_in_memory() {
+    let dir = test_dir("unwritable");
+    let cache_file = dir.join("missing").join("features.bin");
+
+    let (cache, warnings) =
+        FeatureCache::load_or_create(&cache_file, CacheKey::default(), &dataset(&[5.0]));
+
+    assert_eq!(labels(&cache), [5.0]);
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(warnings[0].contains("can not be written"), "{warnings:?}");
+}
+
+#[test]
+fn cache_with_another_key_is_rebuilt() {
+    let dir = test_dir("other-key");
+    let cache_file = dir.join("features.bin");
+
+    FeatureCache::load_or_create(&cache_file, CacheKey::default(), &dataset(&[1.0, 2.0]));
+    let key = CacheKey::default().update(b"changed");
+    assert!(FeatureCache::load(&cache_file, key).unwrap().is_none());
+    let (cache, warnings) = FeatureCache::load_or_create(&cache_file, key, &dataset(&[5.0]));
+
+    assert!(warnings.is_empty(), "{warnings:?}");
+    assert_eq!(labels(&cache), [5.0]);
+    let reloaded = FeatureCache::load(&cache_file, key).unwrap().unwrap();
+    assert_eq!(labels(&reloaded), [5.0]);
+}
+
+fn assert_corrupt_cache_is_rebuilt(cache_file: &Path, key: CacheKey) {
+    assert!(FeatureCache::load(cache_file, key).is_err());
+    let (cache, warnings) = FeatureCache::load_or_create(cache_file, key, &dataset(&[5.0]));
+    assert_eq!(labels(&cache), [5.0]);
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert!(warnings[0].contains("rebuilt"), "{warnings:?}");
+    let reloaded = FeatureCache::load(cache_file, key).unwrap().unwrap();
+    assert_eq!(labels(&reloaded), [5.0]);
+}
+
+#[test]
+fn truncated_cache_is_rebuilt() {
+    let dir = test_dir("truncated");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default();
+    FeatureCache::load_or_create(&cache_file, key, &dataset(&[1.0, 2.0, 3.0]));
+
+    let bytes = std::fs::read(&cache_file).unwrap();
+    std::fs::write(&cache_file, &bytes[..bytes.len() - 5]).unwrap();
+
+    assert_corrupt_cache_is_rebuilt(&cache_file, key);
+}
+
+#[test]
+fn corrupt_row_count_is_rebuilt() {
+    let dir = test_dir("row-count");
+    let cache_file = dir.join("features.bin");
+    let key = CacheKey::default();
+    FeatureCache::load_or_create(&cache_file, key, &dataset(&[1.0, 2.0, 3.0]));
+
+    // The row count follows the magic bytes, the version and the key
+    let mut bytes = std::fs::read(&cache_file).unwrap();
+    bytes[20..28].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&cache_file, bytes).unwrap();
+
+    assert_corrupt_cache_is_rebuilt(&cache_file, key);
+}
+
+const HEADER: &str = "fare_amount,pickup_latitude,pickup_longitude,dropoff_latitude,dropoff_longitude,passenger_count,distance,pickup_hour,pickup_weekday,am_or_pm";
+
+fn write_source(file: &Path, fares: &[f64]) {
+    let rows = fares
+        .iter()
+        .map(|fare| format!("{fare},40.7,-73.9,40.8,-73.95,1,5.2,20,3,1"))
+        .collect::<Vec<_>>();
+    std::fs::write(file, format!("{HEADER}\n{}\n", rows.join("\n"))).unwrap();
+}
+
+fn features(file: &Path, cardinalities: &[usize]) -> Vec<TaxifareDatasetMappedItem> {
+    let builder =
+        TaxifareDatasetBuilder::new(file.to_str().unwrap(), SplitConfig::new(), Some(1), None)
+            .unwrap();
+    let mapper =
+        RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), cardinalities).unwrap();
+    let features = builder.features(&mapper, true).unwrap().0;
+    (0..features.len())
+        .filter_map(|index| features.get(index))
+        .collect()
+}
+
+#[test]
+fn changed_source_file_rebuilds_the_cache() {
+    let dir = test_dir("source");
+    let source = dir.join("trips.csv");
+    write_source(&source, &[4.5, 10.0]);
+    let first = features(&source, &[7, 24, 2]);
+    assert!(dir.join("trips.csv.features.bin").exists());
+
+    write_source(&source, &[4.5, 11.0]);
+    let second = features(&source, &[7, 24, 2]);
+
+    assert_eq!(
+        first.iter().map(|item| item.label).collect::<Vec<_>>(),
+        [4.5, 10.0]
+    );
+    assert_eq!(
+        second.iter().map(|item| item.label).collect::<Vec<_>>(),
+        [4.5, 11.0]
+    );
+}
+
+#[test]
+fn changed_mapper_rebuilds_the_cache() {
+    let dir = test_dir("mapper");
+    let source = dir.join("trips.csv");
+    write_source(&source, &[4.5, 10.0]);
+
+    let all_hours = features(&source, &[7, 24, 2]);
+    // Hour 20 falls into the unknown bucket of only 12 known hours
+    let half_hours = features(&source, &[7, 12, 2]);
+
+    assert!(all_hours.iter().all(|item| item.discrete_hour == 20));
+    assert!(half_hours.iter().all(|item| item.discrete_hour == 12));
+}
+
+#[test]
+fn unreadable_source_file_is_an_error() {
+    let dir = test_dir("missing-source");
+    let source = dir.join("trips.csv");
+    write_source(&source, &[4.5, 10.0]);
+    let builder =
+        TaxifareDatasetBuilder::new(source.to_str().unwrap(), SplitConfig::new(), Some(1), None)
+            .unwrap();
+    let mapper = RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, 24, 2]).unwrap();
+    std::fs::remove_file(&source).unwrap();
+
+    let err = builder.features(&mapper, true).err().unwrap();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    // Without the cache the items are mapped from memory
+    assert_eq!(builder.features(&mapper, false).unwrap().0.len(), 2);
+}
@@ -0,0 +1,127 @@
+use burn::{
+    backend::{Autodiff, NdArray},
+    prelude::*,
+};
+use linear_regression::{loss::LossFunction, models::taxifare_model::ModelConfig};
+
+type B = NdArray;
+
+/// Outputs 3, 0.5 and -1 for the targets 1, 1 and 2, i.e. errors of 2, -0.5 and -3.
+fn batch<B: Backend>() -> (Tensor<B, 2>, Tensor<B, 2>, Tensor<B, 2>) {
+    let device = Default::default();
+    let column =
+        |values: [f32; 3]| Tensor::from_data(TensorData::new(values.to_vec(), [3, 1]), &device);
+    (
+        column([3.0, 0.5, -1.0]),
+        column([1.0, 1.0, 2.0]),
+        column([1.0, 2.0, 1.0]),
+    )
+}
+
+/// Unweighted and weighted loss of the batch.
+fn losses(loss: LossFunction) -> (f32, f32) {
+    let (output, targets, weights) = batch::<B>();
+    let unweighted = loss.forward(output.clone(), targets.clone(), None);
+    let weighted = loss.forward(output, targets, Some(weights));
+    (unweighted.into_scalar(), weighted.into_scalar())
+}
+
+fn assert_losses(loss: LossFunction, unweighted: f32, weighted: f32) {
+    let (actual_unweighted, actual_weighted) = losses(loss);
+    assert!(
+        (actual_unweighted - unweighted).abs() < 1e-5,
+        "{loss}: {actual_unweighted} != {unweighted}"
+    );
+    assert!(
+        (actual_weighted - weighted).abs() < 1e-5,
+        "{loss} weighted: {actual_weighted} != {weighted}"
+    );
+}
+
+#[test]
+fn mse() {
+    // Squared errors 4, 0.25 and 9
+    assert_losses(LossFunction::Mse, 13.25 / 3.0, 13.5 / 4.0);
+}
+
+#[test]
+fn mae() {
+    assert_losses(LossFunction::Mae, 5.5 / 3.0, 6.0 / 4.0);
+}
+
+#[test]
+fn huber() {
+    // 1 * (2 - 0.5), 0.5 * 0.5^2 and 1 * (3 - 0.5)
+    assert_losses(LossFunction::Huber { delta: 1.0 }, 4.125 / 3.0, 4.25 / 4.0);
+    // All errors are within the delta, so this is half the MSE
+    assert_losses(LossFunction::Huber { delta: 5.0 }, 13.25 / 6.0, 13.5 / 8.0);
+}
+
+#[test]
+fn log_cosh() {
+    // log(cosh(2)) = 1.3250027, log(cosh(0.5)) = 0.1201145 and log(cosh(3)) = 2.3093285
+    assert_losses(LossFunction::LogCosh, 1.2514819, 0.9686401);
+}
+
+#[test]
+fn rmsle() {
+    // The output -1 is clamped to 0, the squared log errors are
+    // log(4/2)^2 = 0.4804530, log(1.5/2)^2 = 0.0827610 and log(1/3)^2 = 1.2069490
+    assert_losses(LossFunction::Rmsle, 0.7681499, 0.6806107);
+}
+
+#[test]
+fn rmsle_gradient_is_finite_for_perfect_predictions() {
+    let device = Default::default();
+    let output =
+        Tensor::<Autodiff<B>, 2>::from_data(TensorData::new(vec![2.0f32, 5.0], [2, 1]), &device)
+            .require_grad();
+    let targets = output.clone().detach();
+
+    let loss = LossFunction::Rmsle.forward(output.clone(), targets, None);
+    let gradients = loss.backward();
+    let gradient = output.grad(&gradients).unwrap();
+
+    assert!(gradient.into_data().iter::<f32>().all(f32::is_finite));
+}
+
+#[test]
+fn batch_with_zero_weights_has_no_loss() {
+    let (output, targets, weights) = batch::<B>();
+    for loss in [LossFunction::Mse, LossFunction::Rmsle] {
+        let zero_weights = weights.zeros_like();
+        let value = loss
+            .forward(output.clone(), targets.clone(), Some(zero_weights))
+            .into_scalar();
+        assert!(value.is_finite(), "{loss}: {value}");
+        assert!(value < 1e-3, "{loss}: {value}");
+    }
+}
+
+#[test]
+fn invalid_huber_delta_is_rejected() {
+    for delta in [0.0, -1.0, f64::NAN] {
+        assert!(LossFunction::Huber { delta }.validated().is_err());
+        assert!(
+            ModelConfig::new(vec![(7, 4), (24, 12), (2, 1)], 6, &[16], 0.0)
+                .with_loss(LossFunction::Huber { delta })
+                .is_err()
+        );
+    }
+    let parsed = serde_json::from_str::<LossFunction>(r#"{"Huber": {"delta": -0.5}}"#);
+    assert!(parsed.is_err());
+}
+
+#[test]
+fn valid_losses_survive_a_round_trip() {
+    for loss in [
+        LossFunction::Mse,
+        LossFunction::Mae,
+        LossFunction::Huber { delta: 2.5 },
+        LossFunction::LogCosh,
+        LossFunction::Rmsle,
+    ] {
+        let json = serde_json::to_string(&loss).unwrap();
+        assert_eq!(serde_json::from_str::<LossFunction>(&json).unwrap(), loss);
+    }
+}
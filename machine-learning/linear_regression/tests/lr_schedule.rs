@@ -0,0 +1,208 @@
+use burn::{backend::NdArray, lr_scheduler::LrScheduler};
+use linear_regression::lr_schedule::LrSchedule;
+
+type B = NdArray;
+
+/// Learning rates of the iterations with a base learning rate of 1.
+fn assert_learning_rates(schedule: LrSchedule, expected: &[(usize, f64)]) {
+    for (iteration, learning_rate) in expected {
+        let actual = schedule.learning_rate(1.0, *iteration);
+        assert!(
+            (actual - learning_rate).abs() < 1e-9,
+            "{schedule:?} at iteration {iteration}: {actual} != {learning_rate}"
+        );
+    }
+}
+
+#[test]
+fn constant() {
+    assert_learning_rates(LrSchedule::Constant, &[(0, 1.0), (1_000_000, 1.0)]);
+}
+
+#[test]
+fn step_decay() {
+    assert_learning_rates(
+        LrSchedule::StepDecay {
+            step_size: 10,
+            gamma: 0.5,
+        },
+        &[(0, 1.0), (9, 1.0), (10, 0.5), (25, 0.25)],
+    );
+}
+
+#[test]
+fn exponential() {
+    assert_learning_rates(
+        LrSchedule::Exponential { gamma: 0.9 },
+        &[(0, 1.0), (1, 0.9), (2, 0.81)],
+    );
+}
+
+#[test]
+fn exponential_does_not_wrap_after_many_iterations() {
+    let schedule = LrSchedule::Exponential { gamma: 0.9 };
+    // 2^32 iterations would be 0 as an i32 exponent
+    assert_eq!(schedule.learning_rate(1.0, 1 << 32), 0.0);
+    let schedule = LrSchedule::StepDecay {
+        step_size: 1,
+        gamma: 0.9,
+    };
+    assert_eq!(schedule.learning_rate(1.0, 1 << 32), 0.0);
+}
+
+#[test]
+fn cosine_warm_restarts() {
+    // Periods of 10, 20 and 40 iterations starting at 0, 10 and 30
+    assert_learning_rates(
+        LrSchedule::CosineWarmRestarts {
+            period: 10,
+            period_mult: 2,
+            min_lr: 0.01,
+        },
+        &[
+            (0, 1.0),
+            (5, 0.505),
+            // 0.01 + 0.99 * (1 + cos(0.9 pi)) / 2
+            (9, 0.034227024433899),
+            (10, 1.0),
+            (20, 0.505),
+            (30, 1.0),
+            (50, 0.505),
+            (70, 1.0),
+        ],
+    );
+}
+
+#[test]
+fn cosine_warm_restarts_without_growth() {
+    assert_learning_rates(
+        LrSchedule::CosineWarmRestarts {
+            period: 4,
+            period_mult: 1,
+            min_lr: 0.0,
+        },
+        &[(0, 1.0), (2, 0.5), (4, 1.0), (6, 0.5), (8, 1.0)],
+    );
+}
+
+#[test]
+fn linear_warmup() {
+    assert_learning_rates(
+        LrSchedule::LinearWarmup {
+            warmup_iters: 10,
+            start_factor: 0.1,
+        },
+        &[(0, 0.1), (5, 0.55), (9, 0.91), (10, 1.0), (1000, 1.0)],
+    );
+}
+
+#[test]
+fn one_cycle() {
+    // Warmup over 30 iterations from 1/25 = 0.04, annealing to 0.04 / 1e4 at iteration 100
+    assert_learning_rates(
+        LrSchedule::OneCycle {
+            total_iters: 100,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+        },
+        &[
+            (0, 0.04),
+            (15, 0.52),
+            (30, 1.0),
+            (65, (1.0 + 4e-6) / 2.0),
+            (100, 4e-6),
+            (200, 4e-6),
+        ],
+    );
+}
+
+#[test]
+fn scheduler_steps_through_the_schedule_and_resumes() {
+    let schedule = LrSchedule::StepDecay {
+        step_size: 2,
+        gamma: 0.1,
+    };
+    let mut scheduler = schedule.init(0.5);
+    let learning_rates = (0..3).map(|_| scheduler.step()).collect::<Vec<_>>();
+    assert_eq!(learning_rates, [0.5, 0.5, 0.05]);
+
+    let record = scheduler.to_record::<B>();
+    assert_eq!(record, 3);
+    let mut resumed = schedule.init(0.5).load_record::<B>(record);
+    assert!((resumed.step() - 0.05).abs() < 1e-12);
+    assert!((resumed.step() - 0.005).abs() < 1e-12);
+}
+
+#[test]
+fn cosine_warm_restarts_finds_the_period_of_late_iterations() {
+    // Periods of 10 * 2^k start at 10 * (2^k - 1)
+    let schedule = LrSchedule::CosineWarmRestarts {
+        period: 10,
+        period_mult: 2,
+        min_lr: 0.0,
+    };
+    let start = 10 * ((1usize << 40) - 1);
+    assert_learning_rates(
+        schedule,
+        &[(start - 1, 0.0), (start, 1.0), (start + 5 * (1 << 40), 0.5)],
+    );
+    let schedule = LrSchedule::CosineWarmRestarts {
+        period: 4,
+        period_mult: 1,
+        min_lr: 0.0,
+    };
+    assert_learning_rates(
+        schedule,
+        &[(4_000_000_000_000, 1.0), (4_000_000_000_002, 0.5)],
+    );
+}
+
+#[test]
+fn valid_schedules_can_be_deserialized() {
+    for schedule in [
+        LrSchedule::Constant,
+        LrSchedule::StepDecay {
+            step_size: 10,
+            gamma: 0.5,
+        },
+        LrSchedule::CosineWarmRestarts {
+            period: 10,
+            period_mult: 2,
+            min_lr: 0.0,
+        },
+        LrSchedule::LinearWarmup {
+            warmup_iters: 10,
+            start_factor: 0.0,
+        },
+        LrSchedule::OneCycle {
+            total_iters: 100,
+            pct_start: 0.3,
+            div_factor: 25.0,
+            final_div_factor: 1e4,
+        },
+    ] {
+        let json = serde_json::to_string(&schedule).unwrap();
+        assert_eq!(serde_json::from_str::<LrSchedule>(&json).unwrap(), schedule);
+    }
+}
+
+#[test]
+fn invalid_parameters_are_rejected_on_deserialization() {
+    for json in [
+        r#"{"StepDecay": {"step_size": 0, "gamma": 0.5}}"#,
+        r#"{"StepDecay": {"step_size": 10, "gamma": 0.0}}"#,
+        r#"{"Exponential": {"gamma": -0.9}}"#,
+        r#"{"CosineWarmRestarts": {"period": 0, "period_mult": 2, "min_lr": 0.0}}"#,
+        r#"{"CosineWarmRestarts": {"period": 10, "period_mult": 0, "min_lr": 0.0}}"#,
+        r#"{"CosineWarmRestarts": {"period": 10, "period_mult": 2, "min_lr": -1.0}}"#,
+        r#"{"LinearWarmup": {"warmup_iters": 0, "start_factor": 0.1}}"#,
+        r#"{"LinearWarmup": {"warmup_iters": 10, "start_factor": 2.0}}"#,
+        r#"{"OneCycle": {"total_iters": 100, "pct_start": 0.0, "div_factor": 25.0, "final_div_factor": 1e4}}"#,
+        r#"{"OneCycle": {"total_iters": 100, "pct_start": 1.0, "div_factor": 25.0, "final_div_factor": 1e4}}"#,
+        r#"{"OneCycle": {"total_iters": 100, "pct_start": 0.3, "div_factor": 0.0, "final_div_factor": 1e4}}"#,
+        r#"{"OneCycle": {"total_iters": 100, "pct_start": 0.3, "div_factor": 25.0, "final_div_factor": -1.0}}"#,
+    ] {
+        assert!(serde_json::from_str::<LrSchedule>(json).is_err(), "{json}");
+    }
+}
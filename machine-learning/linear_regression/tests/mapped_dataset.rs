@@ -0,0 +1,113 @@
+use std::io::ErrorKind;
+
+use burn::data::dataset::{InMemDataset, transform::Mapper};
+use linear_regression::dataset::{
+    mapped_dataset::{MAX_CARDINALITY, RawDatafieldToFeaturesMapper, TaxifareDatasetFeaturesItem},
+    raw_dataset::TaxifareDatasetRawItem,
+    scaler::FeatureScaler,
+};
+
+fn trip(pickup_weekday: u8, pickup_hour: u8, am_or_pm: u8) -> TaxifareDatasetRawItem {
+    TaxifareDatasetRawItem {
+        fare_amount: 12.5,
+        pickup_latitude: 40.75,
+        pickup_longitude: -73.98,
+        dropoff_latitude: 40.7,
+        dropoff_longitude: -73.9,
+        passenger_count: Some(1.0),
+        distance: Some(5.2),
+        pickup_hour,
+        pickup_weekday,
+        am_or_pm,
+        pickup_timestamp: None,
+        sample_weight: None,
+    }
+}
+
+fn mapper() -> RawDatafieldToFeaturesMapper {
+    RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, 24, 2]).unwrap()
+}
+
+fn buckets(trip: &TaxifareDatasetRawItem) -> [u8; 3] {
+    let features: TaxifareDatasetFeaturesItem = mapper().map(trip);
+    [
+        features.discrete_weekday,
+        features.discrete_hour,
+        features.discrete_am_or_pm,
+    ]
+}
+
+#[test]
+fn known_values_keep_their_index() {
+    assert_eq!(buckets(&trip(0, 0, 0)), [0, 0, 0]);
+    assert_eq!(buckets(&trip(6, 23, 1)), [6, 23, 1]);
+}
+
+#[test]
+fn unknown_values_are_mapped_to_the_bucket_after_the_known_ones() {
+    assert_eq!(buckets(&trip(7, 24, 2)), [7, 24, 2]);
+    assert_eq!(buckets(&trip(9, 99, u8::MAX)), [7, 24, 2]);
+    assert_eq!(buckets(&trip(3, 30, 1)), [3, 24, 1]);
+}
+
+#[test]
+fn violations_are_reported_with_one_based_rows() {
+    let dataset = InMemDataset::new(vec![
+        trip(3, 20, 1),
+        trip(7, 20, 1),
+        trip(3, 20, 1),
+        trip(8, 25, 0),
+    ]);
+
+    let violations = mapper().categorical_violations(&dataset);
+
+    let reported = violations
+        .iter()
+        .map(|violation| {
+            (
+                violation.row,
+                violation.feature,
+                violation.value,
+                violation.cardinality,
+            )
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        reported,
+        [
+            (2, "pickup_weekday", 7, 7),
+            (4, "pickup_weekday", 8, 7),
+            (4, "pickup_hour", 25, 24),
+        ]
+    );
+    assert_eq!(
+        violations[2].to_string(),
+        "row 4: pickup_hour = 25 is outside of 0..24"
+    );
+}
+
+#[test]
+fn valid_datasets_have_no_violations() {
+    let dataset = InMemDataset::new(vec![trip(0, 0, 0), trip(6, 23, 1)]);
+
+    assert!(mapper().categorical_violations(&dataset).is_empty());
+}
+
+#[test]
+fn one_cardinality_per_categorical_feature_is_required() {
+    for cardinalities in [&[7, 24][..], &[7, 24, 2, 5]] {
+        let err =
+            RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), cardinalities).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
+
+#[test]
+fn cardinalities_must_fit_the_unknown_bucket_into_a_byte() {
+    RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, MAX_CARDINALITY, 2]).unwrap();
+
+    let err =
+        RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, 256, 2]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("pickup_hour"), "{err}");
+}
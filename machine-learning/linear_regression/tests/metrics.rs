@@ -0,0 +1,120 @@
+use burn::{
+    backend::NdArray,
+    prelude::*,
+    train::{
+        RegressionOutput,
+        metric::{Adaptor, Metric, MetricMetadata, Numeric, NumericEntry},
+        renderer::TrainingProgress,
+    },
+};
+use linear_regression::metrics::RegressionMetric;
+
+type B = NdArray;
+
+/// Outputs and targets of one epoch, split into batches of different sizes.
+/// The errors are 2, -2, -1, 0 and 3, the last target is 0.
+const BATCHES: [&[(f32, f32)]; 3] = [
+    &[(12.0, 10.0), (18.0, 20.0)],
+    &[(4.0, 5.0)],
+    &[(8.0, 8.0), (3.0, 0.0)],
+];
+
+fn metadata(iteration: usize) -> MetricMetadata {
+    MetricMetadata {
+        progress: TrainingProgress {
+            items_processed: iteration,
+            items_total: BATCHES.len(),
+        },
+        epoch: 1,
+        epoch_total: 1,
+        iteration,
+        lr: None,
+    }
+}
+
+fn tensor(values: Vec<f32>) -> Tensor<B, 2> {
+    let len = values.len();
+    Tensor::from_data(TensorData::new(values, [len, 1]), &Default::default())
+}
+
+///
+/// Runs the metric over the batches and returns its final value and
+/// the weighted mean of its entries, which is what Burn reports for the epoch.
+///
+fn epoch_values(mut metric: RegressionMetric<B>) -> (f64, f64) {
+    let (mut sum, mut count) = (0.0, 0);
+    for (iteration, batch) in BATCHES.iter().enumerate() {
+        let output = RegressionOutput {
+            loss: Tensor::<B, 1>::zeros([1], &Default::default()),
+            output: tensor(batch.iter().map(|(output, _)| *output).collect()),
+            targets: tensor(batch.iter().map(|(_, target)| *target).collect()),
+        };
+        let entry = metric.update(&output.adapt(), &metadata(iteration + 1));
+        match NumericEntry::deserialize(&entry.serialize).expect("Entry should be numeric") {
+            NumericEntry::Value(value) => {
+                sum += value;
+                count += 1;
+            }
+            NumericEntry::Aggregated {
+                sum: batch_sum,
+                count: batch_count,
+                ..
+            } => {
+                sum += batch_sum;
+                count += batch_count;
+            }
+        }
+    }
+    (metric.value(), sum / count as f64)
+}
+
+fn assert_epoch_value(metric: RegressionMetric<B>, expected: f64) {
+    let (value, mean) = epoch_values(metric);
+    assert!((value - expected).abs() < 1e-9, "{value} != {expected}");
+    assert!((mean - expected).abs() < 1e-9, "{mean} != {expected}");
+}
+
+#[test]
+fn rmse_is_exact_over_batches() {
+    // Squared errors 4 + 4 + 1 + 0 + 9 = 18 over 5 items.
+    assert_epoch_value(RegressionMetric::rmse(), (18.0f64 / 5.0).sqrt());
+}
+
+#[test]
+fn mae_is_exact_over_batches() {
+    assert_epoch_value(RegressionMetric::mae(), 8.0 / 5.0);
+}
+
+#[test]
+fn r2_is_exact_over_batches() {
+    // Targets sum to 43 and their squares to 589, so the total sum of squares is
+    // 589 - 43^2 / 5 = 219.2.
+    assert_epoch_value(RegressionMetric::r2(), 1.0 - 18.0 / 219.2);
+}
+
+#[test]
+fn mape_is_exact_over_batches() {
+    // 2/10, 2/20, 1/5 and 0/8, the target of 0 is skipped.
+    assert_epoch_value(RegressionMetric::mape(), 100.0 * 0.5 / 4.0);
+}
+
+#[test]
+fn median_absolute_error_is_exact_over_batches() {
+    // Absolute errors 0, 1, 2, 2, 3.
+    assert_epoch_value(RegressionMetric::median_absolute_error(), 2.0);
+}
+
+#[test]
+fn clear_starts_a_new_epoch() {
+    let mut metric = RegressionMetric::<B>::mae();
+    let output = RegressionOutput {
+        loss: Tensor::<B, 1>::zeros([1], &Default::default()),
+        output: tensor(vec![1.0]),
+        targets: tensor(vec![4.0]),
+    };
+    metric.update(&output.adapt(), &metadata(1));
+    metric.clear();
+    let (value, mean) = epoch_values(metric);
+    assert!((value - 1.6).abs() < 1e-9);
+    assert!((mean - 1.6).abs() < 1e-9);
+}
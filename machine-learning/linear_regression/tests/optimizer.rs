@@ -0,0 +1,98 @@
+use burn::optim::{
+    AdamConfig, AdamWConfig, GradientClippingConfig, RmsPropConfig, SgdConfig,
+    decay::WeightDecayConfig, momentum::MomentumConfig,
+};
+use linear_regression::{
+    models::taxifare_model::ModelConfig, optimizer::OptimizerConfig, training::TrainingConfig,
+};
+use serde_json::Value;
+
+fn config() -> TrainingConfig {
+    TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[100, 50],
+        0.4,
+    ))
+}
+
+fn optimizers() -> [OptimizerConfig; 4] {
+    [
+        OptimizerConfig::Sgd(
+            SgdConfig::new()
+                .with_momentum(Some(MomentumConfig {
+                    momentum: 0.9,
+                    dampening: 0.0,
+                    nesterov: true,
+                }))
+                .with_weight_decay(Some(WeightDecayConfig::new(1e-4))),
+        ),
+        OptimizerConfig::Adam(
+            AdamConfig::new().with_grad_clipping(Some(GradientClippingConfig::Norm(1.0))),
+        ),
+        OptimizerConfig::AdamW(AdamWConfig::new().with_weight_decay(0.01)),
+        OptimizerConfig::RmsProp(
+            RmsPropConfig::new().with_grad_clipping(Some(GradientClippingConfig::Value(0.5))),
+        ),
+    ]
+}
+
+#[test]
+fn every_optimizer_survives_a_round_trip() {
+    for optimizer in optimizers() {
+        let value = serde_json::to_value(&optimizer).unwrap();
+        let parsed: OptimizerConfig = serde_json::from_value(value.clone()).unwrap();
+        assert_eq!(serde_json::to_value(&parsed).unwrap(), value);
+    }
+}
+
+#[test]
+fn optimizers_are_tagged_by_their_variant() {
+    let names = optimizers().map(|optimizer| {
+        let Value::Object(fields) = serde_json::to_value(&optimizer).unwrap() else {
+            panic!("Optimizers are serialized as objects.");
+        };
+        fields.keys().next().unwrap().clone()
+    });
+
+    assert_eq!(names, ["Sgd", "Adam", "AdamW", "RmsProp"]);
+}
+
+#[test]
+fn configs_without_an_optimizer_default_to_adam() {
+    let mut value = serde_json::to_value(config()).unwrap();
+    value.as_object_mut().unwrap().remove("optimizer");
+
+    let config: TrainingConfig = serde_json::from_value(value).unwrap();
+
+    assert_eq!(
+        serde_json::to_value(&config.optimizer).unwrap(),
+        serde_json::to_value(OptimizerConfig::Adam(AdamConfig::new())).unwrap()
+    );
+}
+
+#[test]
+fn optimizer_fields_can_be_overridden() {
+    let config = config()
+        .with_overrides(&["optimizer.Adam.beta_1=0.8"])
+        .unwrap();
+
+    let value = serde_json::to_value(&config.optimizer).unwrap();
+    // The betas are f32
+    assert!((value["Adam"]["beta_1"].as_f64().unwrap() - 0.8).abs() < 1e-6);
+}
+
+#[test]
+fn optimizer_can_be_replaced() {
+    let adam_w = serde_json::to_string(&OptimizerConfig::AdamW(
+        AdamWConfig::new().with_weight_decay(0.05),
+    ))
+    .unwrap();
+
+    let config = config()
+        .with_overrides(&[format!("optimizer={adam_w}")])
+        .unwrap();
+
+    let value = serde_json::to_value(&config.optimizer).unwrap();
+    assert!((value["AdamW"]["weight_decay"].as_f64().unwrap() - 0.05).abs() < 1e-6);
+}
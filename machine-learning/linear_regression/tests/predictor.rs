@@ -0,0 +1,148 @@
+use std::path::PathBuf;
+
+use burn::{
+    backend::{NdArray, ndarray::NdArrayDevice},
+    config::Config,
+    module::Module,
+    record::CompactRecorder,
+};
+use chrono::DateTime;
+use linear_regression::{
+    dataset::scaler::FeatureScaler,
+    models::taxifare_model::ModelConfig,
+    predictor::{TaxifarePredictor, Trip},
+    training::TrainingConfig,
+};
+use taxifare_features::{TimeFeatures, haversine_distance};
+
+type B = NdArray;
+
+/// Directory with an untrained model, enough to check the shape of the predictions.
+fn test_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("taxifare-predictor-{}-{name}", std::process::id()));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("Test directory can not be created.");
+
+    let config = TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[16, 8],
+        0.4,
+    ));
+    config
+        .save(dir.join("config.json"))
+        .expect("Config can not be saved.");
+    config
+        .model
+        .init::<B>(&NdArrayDevice::Cpu)
+        .save_file(dir.join("model"), &CompactRecorder::new())
+        .expect("Model can not be saved.");
+    FeatureScaler::default()
+        .save(dir.to_str().unwrap())
+        .expect("Scaler can not be saved.");
+    dir
+}
+
+fn trip(pickup_datetime: &str) -> Trip {
+    Trip {
+        pickup_latitude: 40.7614,
+        pickup_longitude: -73.9776,
+        dropoff_latitude: 40.6413,
+        dropoff_longitude: -73.7781,
+        pickup_datetime: DateTime::parse_from_rfc3339(pickup_datetime).unwrap(),
+        passenger_count: 2.0,
+    }
+}
+
+#[test]
+fn time_features_are_local_to_new_york() {
+    // 08:17 in New York during daylight saving time, a Monday
+    let item = trip("2010-04-19T12:17:56Z").to_raw_item();
+
+    assert_eq!(
+        (item.pickup_hour, item.pickup_weekday, item.am_or_pm),
+        (8, 0, 0)
+    );
+    assert_eq!(item.pickup_timestamp, Some(1_271_679_476));
+}
+
+#[test]
+fn pickup_can_fall_on_the_previous_local_day() {
+    // Monday in UTC, but still Sunday 22:30 in New York during standard time
+    let item = trip("2013-01-07T03:30:00Z").to_raw_item();
+
+    assert_eq!(
+        (item.pickup_hour, item.pickup_weekday, item.am_or_pm),
+        (22, 6, 1)
+    );
+    assert_eq!(item.pickup_timestamp, Some(1_357_529_400));
+}
+
+#[test]
+fn same_instant_in_any_timezone_has_the_same_features() {
+    let utc = trip("2010-04-19T12:17:56Z").to_raw_item();
+    let new_york = trip("2010-04-19T08:17:56-04:00").to_raw_item();
+    let tokyo = trip("2010-04-19T21:17:56+09:00").to_raw_item();
+
+    assert_eq!(format!("{new_york:?}"), format!("{utc:?}"));
+    assert_eq!(format!("{tokyo:?}"), format!("{utc:?}"));
+}
+
+#[test]
+fn features_match_the_shared_definitions() {
+    for pickup_datetime in [
+        "2009-06-15T17:26:21Z",
+        "2011-08-18T00:35:00+02:00",
+        "2012-11-04T05:30:00Z",
+        "2014-12-31T23:59:59-05:00",
+    ] {
+        let trip = trip(pickup_datetime);
+        let item = trip.to_raw_item();
+        let time = TimeFeatures::new(&trip.pickup_datetime);
+
+        assert_eq!(item.pickup_hour, time.pickup_hour, "{pickup_datetime}");
+        assert_eq!(
+            item.pickup_weekday, time.pickup_weekday,
+            "{pickup_datetime}"
+        );
+        assert_eq!(item.am_or_pm, time.am_or_pm, "{pickup_datetime}");
+        assert_eq!(item.pickup_timestamp, Some(time.pickup_timestamp));
+        assert_eq!(
+            item.distance,
+            Some(haversine_distance(
+                trip.pickup_latitude,
+                trip.pickup_longitude,
+                trip.dropoff_latitude,
+                trip.dropoff_longitude
+            ))
+        );
+        assert_eq!(item.passenger_count, Some(trip.passenger_count));
+        assert_eq!(item.sample_weight, None);
+    }
+}
+
+#[test]
+fn single_and_batched_predictions_agree() {
+    let dir = test_dir("batch");
+    let predictor = TaxifarePredictor::<B>::load(dir.to_str().unwrap(), NdArrayDevice::Cpu)
+        .unwrap()
+        .with_batch_size(2);
+    let trips = (0..5)
+        .map(|hour| trip(&format!("2013-07-01T1{hour}:30:00Z")))
+        .collect::<Vec<_>>();
+
+    let batched = predictor.predict_batch(&trips);
+
+    assert_eq!(batched.len(), trips.len());
+    for (trip, fare) in trips.iter().zip(&batched) {
+        assert!((predictor.predict(trip) - fare).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn missing_artifact_directory_is_an_error() {
+    let dir = test_dir("missing").join("missing");
+
+    assert!(TaxifarePredictor::<B>::load(dir.to_str().unwrap(), NdArrayDevice::Cpu).is_err());
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use burn::data::dataset::Dataset;
+use linear_regression::dataset::{
+    mapped_dataset::{RawDatafieldToFeaturesMapper, TaxifareDatasetMappedItem},
+    raw_dataset::convert_csv_to_sqlite,
+    sample_weights::SampleWeighting,
+    scaler::FeatureScaler,
+    taxifare_dataset::{SplitConfig, TaxifareDatasetBuilder},
+};
+
+/// Trips with missing passenger counts, distances and weights.
+const TRIPS: &str = "\
+fare_amount,pickup_latitude,pickup_longitude,dropoff_latitude,dropoff_longitude,passenger_count,distance,pickup_hour,pickup_weekday,am_or_pm,tip
+4.5,40.73,-73.99,40.72,-73.98,1,1.03,4,1,0,2.5
+16.9,40.76,-73.97,40.74,-73.99,,8.45,16,4,1,
+5.7,40.75,-73.98,40.76,-73.99,2,,11,0,0,0.5
+7.7,40.77,-73.96,40.79,-73.95,,,21,6,1,
+";
+
+/// Empty directory which is unique to the test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "taxifare-raw-dataset-{}-{name}",
+        std::process::id()
+    ));
+    std::fs::remove_dir_all(&dir).ok();
+    std::fs::create_dir_all(&dir).expect("Test directory can not be created.");
+    dir
+}
+
+/// Mapped items in file order without scaling, weighted by the tip column.
+fn items(file: &str) -> Vec<TaxifareDatasetMappedItem> {
+    let builder = TaxifareDatasetBuilder::new(file, SplitConfig::new(), Some(1), Some("tip"))
+        .expect("Dataset can not be loaded.");
+    let mapper = RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, 24, 2])
+        .unwrap()
+        .with_sample_weights(
+            builder
+                .sample_weights(SampleWeighting::Column("tip".to_string()))
+                .unwrap(),
+        );
+    let (features, _) = builder.features(&mapper, false).unwrap();
+    (0..features.len())
+        .filter_map(|index| features.get(index))
+        .collect()
+}
+
+#[test]
+fn sqlite_database_holds_the_items_of_the_csv_file() {
+    let dir = test_dir("convert");
+    let csv_file = dir.join("trips.csv");
+    let db_file = dir.join("trips.db");
+    std::fs::write(&csv_file, TRIPS).unwrap();
+
+    let count = convert_csv_to_sqlite(
+        csv_file.to_str().unwrap(),
+        db_file.to_str().unwrap(),
+        Some("tip"),
+    )
+    .unwrap();
+
+    assert_eq!(count, 4);
+    let from_csv = items(csv_file.to_str().unwrap());
+    let from_sqlite = items(db_file.to_str().unwrap());
+    assert_eq!(from_sqlite.len(), from_csv.len());
+    for (sqlite_item, csv_item) in from_sqlite.iter().zip(&from_csv) {
+        assert_eq!(format!("{sqlite_item:?}"), format!("{csv_item:?}"));
+    }
+}
+
+#[test]
+fn missing_values_survive_the_conversion() {
+    let dir = test_dir("missing");
+    let csv_file = dir.join("trips.csv");
+    let db_file = dir.join("trips.sqlite");
+    std::fs::write(&csv_file, TRIPS).unwrap();
+    convert_csv_to_sqlite(
+        csv_file.to_str().unwrap(),
+        db_file.to_str().unwrap(),
+        Some("tip"),
+    )
+    .unwrap();
+
+    let items = items(db_file.to_str().unwrap());
+
+    // The passenger count and the distance are the last two continuous features
+    let masks = items
+        .iter()
+        .map(|item| (item.continuous_mask[4], item.continuous_mask[5]))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        masks,
+        [(true, true), (false, true), (true, false), (false, false)]
+    );
+    let weights = items.iter().map(|item| item.weight).collect::<Vec<_>>();
+    assert_eq!(weights, [Some(2.5), Some(1.0), Some(0.5), Some(1.0)]);
+    let labels = items.iter().map(|item| item.label).collect::<Vec<_>>();
+    assert_eq!(labels, [4.5, 16.9, 5.7, 7.7]);
+}
+
+#[test]
+fn unknown_weight_column_is_rejected() {
+    let dir = test_dir("weight-column");
+    let csv_file = dir.join("trips.csv");
+    std::fs::write(&csv_file, TRIPS).unwrap();
+
+    let err = convert_csv_to_sqlite(
+        csv_file.to_str().unwrap(),
+        dir.join("trips.db").to_str().unwrap(),
+        Some("weight"),
+    )
+    .unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
@@ -0,0 +1,90 @@
+use linear_regression::dataset::{
+    raw_dataset::TaxifareDatasetRawItem,
+    sample_weights::{SampleWeighting, SampleWeights},
+};
+
+const DAY: i64 = 24 * 60 * 60;
+/// Most recent pickup of the data, 2015-01-01.
+const LATEST: i64 = 1_420_070_400;
+
+fn trip(pickup_timestamp: Option<i64>, sample_weight: Option<f64>) -> TaxifareDatasetRawItem {
+    TaxifareDatasetRawItem {
+        fare_amount: 12.5,
+        pickup_latitude: 40.75,
+        pickup_longitude: -73.98,
+        dropoff_latitude: 40.7,
+        dropoff_longitude: -73.9,
+        passenger_count: Some(1.0),
+        distance: Some(5.2),
+        pickup_hour: 20,
+        pickup_weekday: 3,
+        am_or_pm: 1,
+        pickup_timestamp,
+        sample_weight,
+    }
+}
+
+#[test]
+fn uniform_weighting_has_no_weights() {
+    let weights = SampleWeights::new(SampleWeighting::Uniform, LATEST);
+
+    assert_eq!(weights.weight(&trip(Some(LATEST), Some(3.0))), None);
+}
+
+#[test]
+fn column_weights_are_read_from_the_item() {
+    let weights = SampleWeights::new(SampleWeighting::Column("sample_weight".into()), 0);
+
+    assert_eq!(weights.weight(&trip(None, Some(3.0))), Some(3.0));
+    assert_eq!(weights.weight(&trip(None, Some(0.0))), Some(0.0));
+    // Rows without a value are weighted like uniform ones
+    assert_eq!(weights.weight(&trip(None, None)), Some(1.0));
+}
+
+#[test]
+fn recency_weights_halve_every_half_life() {
+    let weights = SampleWeights::new(
+        SampleWeighting::RecencyDecay {
+            half_life_days: 30.0,
+        },
+        LATEST,
+    );
+    let weight = |age_days: i64| {
+        weights
+            .weight(&trip(Some(LATEST - age_days * DAY), None))
+            .unwrap()
+    };
+
+    assert_eq!(weight(0), 1.0);
+    assert!((weight(15) - 0.5f64.sqrt()).abs() < 1e-12);
+    assert!((weight(30) - 0.5).abs() < 1e-12);
+    assert!((weight(90) - 0.125).abs() < 1e-12);
+    // Trips without a pickup time are not decayed
+    assert_eq!(weights.weight(&trip(None, None)), Some(1.0));
+}
+
+#[test]
+fn invalid_half_lives_are_rejected_on_deserialization() {
+    for half_life_days in ["0.0", "-7.0"] {
+        let json = format!(r#"{{"RecencyDecay": {{"half_life_days": {half_life_days}}}}}"#);
+        let err = serde_json::from_str::<SampleWeighting>(&json).unwrap_err();
+        assert!(err.to_string().contains("half life"), "{json}: {err}");
+    }
+}
+
+#[test]
+fn valid_weightings_survive_a_round_trip() {
+    for weighting in [
+        SampleWeighting::Uniform,
+        SampleWeighting::Column("sample_weight".into()),
+        SampleWeighting::RecencyDecay {
+            half_life_days: 30.0,
+        },
+    ] {
+        let json = serde_json::to_string(&weighting).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SampleWeighting>(&json).unwrap(),
+            weighting
+        );
+    }
+}
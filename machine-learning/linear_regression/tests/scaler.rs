@@ -0,0 +1,122 @@
+use burn::data::dataset::InMemDataset;
+use linear_regression::dataset::{
+    raw_dataset::TaxifareDatasetRawItem,
+    scaler::{FeatureScaler, ScalerKind},
+};
+
+/// Trips whose pickup latitudes are 1 to 5 and pickup longitudes 1 to 4 and 100.
+/// The passenger count is constant, the distance of every other trip is missing.
+fn trips() -> InMemDataset<TaxifareDatasetRawItem> {
+    let longitudes = [1.0, 2.0, 3.0, 4.0, 100.0];
+    InMemDataset::new(
+        (0..5)
+            .map(|index| TaxifareDatasetRawItem {
+                fare_amount: 10.0,
+                pickup_latitude: index as f64 + 1.0,
+                pickup_longitude: longitudes[index],
+                dropoff_latitude: 40.7,
+                dropoff_longitude: -73.9,
+                passenger_count: Some(1.0),
+                distance: (index % 2 == 0).then_some(2.0 * index as f64 + 2.0),
+                pickup_hour: 20,
+                pickup_weekday: 3,
+                am_or_pm: 1,
+                pickup_timestamp: None,
+                sample_weight: None,
+            })
+            .collect(),
+    )
+}
+
+fn assert_features(actual: [f64; 6], expected: [f64; 6]) {
+    for (index, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "feature {index}: {actual} != {expected}"
+        );
+    }
+}
+
+#[test]
+fn standard_scaler_centers_on_the_mean_and_scales_by_the_standard_deviation() {
+    let scaler = FeatureScaler::fit(ScalerKind::Standard, &trips());
+
+    assert_eq!(scaler.kind(), ScalerKind::Standard);
+    // Latitudes have a mean of 3 and a population standard deviation of sqrt(2),
+    // longitudes a mean of 22 and sqrt(1522), the distances 2, 6 and 10 a mean of 6
+    // and sqrt(32 / 3)
+    let longitude_std = 1522.0f64.sqrt();
+    assert_features(
+        scaler.transform([
+            Some(3.0 + 2.0f64.sqrt()),
+            Some(22.0 + longitude_std),
+            Some(40.7),
+            Some(-73.9),
+            Some(3.0),
+            Some(6.0 - (32.0f64 / 3.0).sqrt()),
+        ]),
+        // Constant features are only centered
+        [1.0, 1.0, 0.0, 0.0, 2.0, -1.0],
+    );
+}
+
+#[test]
+fn robust_scaler_centers_on_the_median_and_scales_by_the_interquartile_range() {
+    let scaler = FeatureScaler::fit(ScalerKind::Robust, &trips());
+
+    assert_eq!(scaler.kind(), ScalerKind::Robust);
+    // Latitudes and longitudes both have a median of 3 and quartiles of 2 and 4,
+    // the outlying longitude of 100 does not change them.
+    // Distances 2, 6 and 10 have a median of 6 and quartiles of 4 and 8.
+    assert_features(
+        scaler.transform([
+            Some(5.0),
+            Some(1.0),
+            Some(40.7),
+            Some(-73.9),
+            Some(1.0),
+            Some(10.0),
+        ]),
+        [1.0, -1.0, 0.0, 0.0, 0.0, 1.0],
+    );
+}
+
+#[test]
+fn missing_values_are_imputed_with_the_center() {
+    for kind in [ScalerKind::Standard, ScalerKind::Robust] {
+        let scaler = FeatureScaler::fit(kind, &trips());
+
+        assert_features(scaler.transform([None; 6]), [0.0; 6]);
+    }
+}
+
+#[test]
+fn default_scaler_keeps_the_values() {
+    let values = [40.75, -73.98, 40.7, -73.9, 2.0, 5.2];
+
+    assert_features(FeatureScaler::default().transform(values.map(Some)), values);
+}
+
+#[test]
+fn save_and_load_round_trip() {
+    let dir = std::env::temp_dir().join(format!("taxifare-scaler-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let artifact_dir = dir.to_str().unwrap();
+    let scaler = FeatureScaler::fit(ScalerKind::Robust, &trips());
+
+    scaler.save(artifact_dir).unwrap();
+    let loaded = FeatureScaler::load(artifact_dir).unwrap();
+
+    assert!(dir.join("scaler.json").exists());
+    assert_eq!(loaded, scaler);
+}
+
+#[test]
+fn missing_scaler_file_is_an_error() {
+    let dir = std::env::temp_dir().join(format!("taxifare-scaler-missing-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let err = FeatureScaler::load(dir.to_str().unwrap()).unwrap_err();
+
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}
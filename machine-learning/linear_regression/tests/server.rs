@@ -0,0 +1,249 @@
+use std::time::Duration;
+
+use axum::{
+    Router,
+    body::Body,
+    http::{Request, StatusCode, header},
+};
+use burn::{
+    backend::{NdArray, ndarray::NdArrayDevice},
+    config::Config,
+    module::Module,
+    record::CompactRecorder,
+};
+use http_body_util::BodyExt;
+use linear_regression::{
+    dataset::scaler::FeatureScaler,
+    models::taxifare_model::ModelConfig,
+    server::{ServerState, batching::BatchingConfig, router},
+    training::TrainingConfig,
+};
+use serde_json::{Value, json};
+use tower::ServiceExt;
+
+type B = NdArray;
+
+/// Artifact directory with an untrained model, enough to exercise the server.
+fn artifact_dir(name: &str) -> String {
+    let dir = std::env::temp_dir().join(format!("taxifare-server-{}-{name}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("Artifact directory can not be created.");
+    let dir = dir
+        .to_str()
+        .expect("Temp dir should be valid UTF-8")
+        .to_string();
+
+    let config = TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[16, 8],
+        0.4,
+    ));
+    config
+        .save(format!("{dir}/config.json"))
+        .expect("Config can not be saved.");
+    config
+        .model
+        .init::<B>(&NdArrayDevice::Cpu)
+        .save_file(format!("{dir}/model"), &CompactRecorder::new())
+        .expect("Model can not be saved.");
+    FeatureScaler::default()
+        .save(&dir)
+        .expect("Scaler can not be saved.");
+    dir
+}
+
+fn app(name: &str) -> Router {
+    app_with_batching(name, BatchingConfig::default())
+}
+
+fn app_with_batching(name: &str, batching: BatchingConfig) -> Router {
+    let state = ServerState::load::<B>(&artifact_dir(name), NdArrayDevice::Cpu, batching)
+        .expect("Server state can not be loaded.");
+    router(state)
+}
+
+fn trip() -> Value {
+    json!({
+        "pickup_latitude": 40.730521,
+        "pickup_longitude": -73.992365,
+        "dropoff_latitude": 40.744746,
+        "dropoff_longitude": -73.975499,
+        "pickup_datetime": "2010-04-19T08:17:56Z",
+        "passenger_count": 1.0
+    })
+}
+
+async fn send(app: Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = app.oneshot(request).await.expect("Request can not fail.");
+    let status = response.status();
+    let body = response
+        .into_body()
+        .collect()
+        .await
+        .expect("Body can be read.")
+        .to_bytes();
+    let body = serde_json::from_slice(&body).expect("Body should be JSON.");
+    (status, body)
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+fn post_predict(body: impl Into<Body>) -> Request<Body> {
+    Request::post("/predict")
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(body.into())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn health_is_ok() {
+    let (status, body) = send(app("health"), get("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body, json!({ "status": "ok" }));
+}
+
+#[tokio::test]
+async fn model_returns_config() {
+    let (status, body) = send(app("model"), get("/model")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["config"]["model"]["layers"], json!([16, 8]));
+    assert_eq!(body["config"]["batch_size"], json!(256));
+    assert_eq!(body["early_stopping"], Value::Null);
+}
+
+#[tokio::test]
+async fn predicts_single_trip_and_batches() {
+    let app = app("predict");
+    let (status, single) = send(app.clone(), post_predict(trip().to_string())).await;
+    assert_eq!(status, StatusCode::OK);
+    let fare = single["fare"].as_f64().expect("Fare should be a number");
+    assert!(fare.is_finite());
+
+    let mut other = trip();
+    other["pickup_datetime"] = json!("2010-04-17T18:43:53-04:00");
+    let batch = json!([trip(), other]);
+    let (status, batch) = send(app, post_predict(batch.to_string())).await;
+    assert_eq!(status, StatusCode::OK);
+    let fares = batch["fares"].as_array().expect("Fares should be an array");
+    assert_eq!(fares.len(), 2);
+    assert!((fares[0].as_f64().unwrap() - fare).abs() < 1e-5);
+}
+
+#[tokio::test]
+async fn malformed_json_is_bad_request() {
+    let (status, body) = send(app("malformed"), post_predict("{\"pickup_latitude\": ")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["error"]["code"], json!("invalid_json"));
+}
+
+#[tokio::test]
+async fn invalid_trips_are_reported_per_field() {
+    let mut out_of_range = trip();
+    out_of_range["pickup_latitude"] = json!(91.0);
+    out_of_range["passenger_count"] = json!(-1.0);
+    let mut missing = trip();
+    missing.as_object_mut().unwrap().remove("pickup_datetime");
+    let batch = json!([trip(), out_of_range, missing]);
+
+    let (status, body) = send(app("invalid"), post_predict(batch.to_string())).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"]["code"], json!("invalid_trip"));
+    let details = body["error"]["details"].as_array().unwrap();
+    assert_eq!(details.len(), 3);
+    assert_eq!(details[0]["index"], json!(1));
+    assert_eq!(details[0]["field"], json!("pickup_latitude"));
+    assert_eq!(details[1]["field"], json!("passenger_count"));
+    assert_eq!(details[2]["index"], json!(2));
+    assert!(
+        details[2]["message"]
+            .as_str()
+            .unwrap()
+            .contains("pickup_datetime")
+    );
+}
+
+#[tokio::test]
+async fn empty_batch_is_rejected() {
+    let (status, body) = send(app("empty"), post_predict("[]")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["error"]["code"], json!("empty_batch"));
+}
+
+#[tokio::test]
+async fn concurrent_requests_share_a_micro_batch() {
+    let app = app_with_batching(
+        "batching",
+        BatchingConfig {
+            max_batch_size: 8,
+            max_wait: Duration::from_millis(500),
+        },
+    );
+    let batch = json!([trip(), trip(), trip()]);
+    let (single, other, batch) = tokio::join!(
+        send(app.clone(), post_predict(trip().to_string())),
+        send(app.clone(), post_predict(trip().to_string())),
+        send(app.clone(), post_predict(batch.to_string())),
+    );
+    assert_eq!(single.0, StatusCode::OK);
+    assert_eq!(other.0, StatusCode::OK);
+    assert_eq!(batch.0, StatusCode::OK);
+    assert_eq!(single.1["fare"], other.1["fare"]);
+    assert_eq!(batch.1["fares"].as_array().unwrap().len(), 3);
+
+    let (status, metrics) = send(app, get("/metrics")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(metrics["max_batch_size"], json!(8));
+    assert_eq!(metrics["latency_ms"]["count"], json!(3));
+    // All 5 trips are queued well within the wait time.
+    assert_eq!(metrics["batch_size"]["count"], json!(1));
+    assert_eq!(metrics["batch_size"]["sum"], json!(5.0));
+}
+
+#[tokio::test]
+async fn micro_batches_respect_max_batch_size() {
+    let app = app_with_batching(
+        "max-batch-size",
+        BatchingConfig {
+            max_batch_size: 2,
+            max_wait: Duration::from_millis(500),
+        },
+    );
+    let (first, second, third) = tokio::join!(
+        send(app.clone(), post_predict(trip().to_string())),
+        send(app.clone(), post_predict(trip().to_string())),
+        send(app.clone(), post_predict(trip().to_string())),
+    );
+    assert_eq!(first.0, StatusCode::OK);
+    assert_eq!(second.0, StatusCode::OK);
+    assert_eq!(third.0, StatusCode::OK);
+
+    let (_, metrics) = send(app, get("/metrics")).await;
+    assert_eq!(metrics["batch_size"]["count"], json!(2));
+    // Buckets of at most 1 and at most 2 trips.
+    assert_eq!(metrics["batch_size"]["counts"][0], json!(1));
+    assert_eq!(metrics["batch_size"]["counts"][1], json!(1));
+}
+
+#[tokio::test]
+async fn large_requests_record_every_forward_pass() {
+    let app = app_with_batching(
+        "forward-passes",
+        BatchingConfig {
+            max_batch_size: 2,
+            max_wait: Duration::from_millis(1),
+        },
+    );
+    let batch = json!([trip(), trip(), trip(), trip(), trip()]);
+    let (status, body) = send(app.clone(), post_predict(batch.to_string())).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["fares"].as_array().unwrap().len(), 5);
+
+    let (_, metrics) = send(app, get("/metrics")).await;
+    // Forward passes of 2, 2 and 1 trips.
+    assert_eq!(metrics["batch_size"]["count"], json!(3));
+    assert_eq!(metrics["batch_size"]["sum"], json!(5.0));
+    assert_eq!(metrics["batch_size"]["counts"][0], json!(1));
+    assert_eq!(metrics["batch_size"]["counts"][1], json!(2));
+}
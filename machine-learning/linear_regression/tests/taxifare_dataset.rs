@@ -0,0 +1,119 @@
+use std::collections::HashSet;
+
+use burn::data::dataset::Dataset;
+use linear_regression::dataset::{
+    mapped_dataset::{RawDatafieldToFeaturesMapper, TaxifareDatasetMappedItem},
+    scaler::FeatureScaler,
+    taxifare_dataset::{FareOversampling, SplitConfig, SplitStrategy, TaxifareDatasetBuilder},
+};
+use polars::prelude::*;
+
+const TRIPS: usize = 200;
+
+/// Trips with the distinct fares 1 to 200, so every fare identifies its trip.
+fn trips() -> DataFrame {
+    let column = |name: &str, value: f64| Column::new(name.into(), vec![value; TRIPS]);
+    DataFrame::new(vec![
+        Column::new(
+            "fare_amount".into(),
+            (1..=TRIPS).map(|fare| fare as f64).collect::<Vec<_>>(),
+        ),
+        column("pickup_latitude", 40.75),
+        column("pickup_longitude", -73.98),
+        column("dropoff_latitude", 40.7),
+        column("dropoff_longitude", -73.9),
+        column("passenger_count", 1.0),
+        column("distance", 5.2),
+        Column::new("pickup_hour".into(), vec![20i64; TRIPS]),
+        Column::new("pickup_weekday".into(), vec![3i64; TRIPS]),
+        Column::new("am_or_pm".into(), vec![1i64; TRIPS]),
+    ])
+    .unwrap()
+}
+
+/// Fares of the training and the test split in dataset order.
+fn split_fares(split: SplitConfig, seed: u64) -> (Vec<f64>, Vec<f64>) {
+    let builder = TaxifareDatasetBuilder::from_dataframe(&trips(), split, Some(seed)).unwrap();
+    let mapper = RawDatafieldToFeaturesMapper::new(FeatureScaler::default(), &[7, 24, 2]).unwrap();
+    let features = builder.features(&mapper, false).unwrap().0;
+    let fares = |dataset: &dyn Dataset<TaxifareDatasetMappedItem>| {
+        (0..dataset.len())
+            .filter_map(|index| dataset.get(index))
+            .map(|item| item.label)
+            .collect::<Vec<_>>()
+    };
+    (
+        fares(&builder.train(&features)),
+        fares(&builder.test(&features)),
+    )
+}
+
+fn stratified() -> SplitConfig {
+    SplitConfig::new().with_strategy(SplitStrategy::StratifiedByFare { buckets: 4 })
+}
+
+fn oversampled() -> SplitConfig {
+    stratified().with_oversampling(Some(FareOversampling {
+        fare_edges: vec![150.0],
+        min_bucket_share: 1.0,
+    }))
+}
+
+#[test]
+fn stratified_split_keeps_the_proportion_of_every_bucket() {
+    let (train, test) = split_fares(stratified(), 7);
+
+    assert_eq!(train.len() + test.len(), TRIPS);
+    // The quartiles hold the fares 1-50, 51-100, 101-150 and 151-200,
+    // 75 % of the 50 trips of every quartile are used for training.
+    for quartile in 0..4 {
+        let fares = (quartile * 50 + 1) as f64..=(quartile * 50 + 50) as f64;
+        let count = |fares_of_split: &[f64]| {
+            fares_of_split
+                .iter()
+                .filter(|fare| fares.contains(fare))
+                .count()
+        };
+        assert_eq!(count(&train), 37, "train trips of quartile {quartile}");
+        assert_eq!(count(&test), 13, "test trips of quartile {quartile}");
+    }
+}
+
+#[test]
+fn split_is_deterministic_for_a_seed() {
+    for split in [SplitConfig::new(), stratified(), oversampled()] {
+        assert_eq!(split_fares(split.clone(), 7), split_fares(split.clone(), 7));
+        assert_ne!(split_fares(split.clone(), 7), split_fares(split, 8));
+    }
+}
+
+#[test]
+fn oversampling_only_repeats_training_trips() {
+    let (train, test) = split_fares(stratified(), 7);
+    let (oversampled_train, oversampled_test) = split_fares(oversampled(), 7);
+
+    // The test split keeps its natural distribution
+    assert_eq!(oversampled_test, test);
+    let test = test.iter().map(|fare| *fare as u64).collect::<HashSet<_>>();
+    assert!(
+        oversampled_train
+            .iter()
+            .all(|fare| !test.contains(&(*fare as u64)))
+    );
+    let unique_train = |fares: &[f64]| {
+        fares
+            .iter()
+            .map(|fare| *fare as u64)
+            .collect::<HashSet<_>>()
+    };
+    assert_eq!(unique_train(&oversampled_train), unique_train(&train));
+
+    // The rare bucket of fares from 150 is repeated up to the size of the other one
+    let expensive = oversampled_train
+        .iter()
+        .filter(|fare| **fare >= 150.0)
+        .count();
+    let cheap = oversampled_train.len() - expensive;
+    assert_eq!(expensive, cheap);
+    assert!(oversampled_train.len() > train.len());
+}
@@ -0,0 +1,136 @@
+use std::io::ErrorKind;
+
+use linear_regression::{
+    dataset::scaler::ScalerKind,
+    models::taxifare_model::ModelConfig,
+    training::{RESUMABLE_FIELDS, TrainingConfig},
+};
+
+fn config() -> TrainingConfig {
+    TrainingConfig::new(ModelConfig::new(
+        vec![(7, 4), (24, 12), (2, 1)],
+        6,
+        &[100, 50],
+        0.4,
+    ))
+}
+
+#[test]
+fn overrides_set_top_level_and_dotted_keys() {
+    let config = config()
+        .with_overrides(&[
+            "num_epochs=20",
+            "learning_rate=0.01",
+            "model.dropout=0.2",
+            "model.layers.0=200",
+            "split.train_percentage=80",
+        ])
+        .unwrap();
+
+    assert_eq!(config.num_epochs, 20);
+    assert_eq!(config.learning_rate, 0.01);
+    assert_eq!(config.model.dropout, 0.2);
+    assert_eq!(config.model.layers, [200, 50]);
+    assert_eq!(config.split.train_percentage, 80);
+}
+
+#[test]
+fn override_values_without_quotes_are_strings() {
+    let config = config()
+        .with_overrides(&[
+            "scaler=Robust",
+            r#"lr_schedule={"Exponential": {"gamma": 0.99}}"#,
+        ])
+        .unwrap();
+
+    assert_eq!(config.scaler, ScalerKind::Robust);
+    assert_eq!(
+        serde_json::to_value(&config.lr_schedule).unwrap(),
+        serde_json::json!({"Exponential": {"gamma": 0.99}})
+    );
+}
+
+#[test]
+fn overrides_replace_optional_values() {
+    let config = config()
+        .with_overrides(&[r#"early_stopping={"metric": "RMSE", "direction": "Lowest", "patience": 3, "min_delta": 0.0}"#])
+        .unwrap();
+    assert_eq!(config.early_stopping.unwrap().patience, 3);
+
+    let config = config().with_overrides(&["feature_cache=false"]).unwrap();
+    assert_eq!(config.feature_cache, Some(false));
+}
+
+#[test]
+fn unknown_keys_are_rejected() {
+    for key in [
+        "epochs=3",
+        "model.dropuot=0.2",
+        "model.layers.5=10",
+        "num_epochs.value=3",
+    ] {
+        let err = config().with_overrides(&[key]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{key}");
+        assert!(
+            err.to_string().contains("Unknown config key"),
+            "{key}: {err}"
+        );
+    }
+}
+
+#[test]
+fn mismatching_types_are_rejected() {
+    for assignment in [
+        "num_epochs=many",
+        "num_epochs=-1",
+        "model.dropout=high",
+        "model.layers=3",
+        "scaler=Quantile",
+    ] {
+        let err = config().with_overrides(&[assignment]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{assignment}");
+        assert!(
+            err.to_string().contains("Invalid override"),
+            "{assignment}: {err}"
+        );
+    }
+}
+
+#[test]
+fn assignments_need_a_value() {
+    let err = config().with_overrides(&["num_epochs"]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+}
+
+#[test]
+fn resumable_fields_may_differ_on_resume() {
+    let saved = config();
+    let resumed = config()
+        .with_overrides(&["num_epochs=50", "num_workers=4", "feature_cache=true"])
+        .unwrap();
+    assert_eq!(
+        RESUMABLE_FIELDS,
+        ["num_epochs", "num_workers", "feature_cache"]
+    );
+
+    resumed.check_resumable(&saved).unwrap();
+}
+
+#[test]
+fn other_fields_may_not_differ_on_resume() {
+    let saved = config();
+    for assignment in [
+        "seed=7",
+        "batch_size=64",
+        "learning_rate=0.01",
+        "scaler=Robust",
+        "model.dropout=0.2",
+        "split.train_percentage=80",
+    ] {
+        let resumed = config().with_overrides(&[assignment]).unwrap();
+        let err = resumed.check_resumable(&saved).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{assignment}");
+        let field = assignment.split(['.', '=']).next().unwrap();
+        assert!(err.to_string().contains(field), "{assignment}: {err}");
+    }
+}
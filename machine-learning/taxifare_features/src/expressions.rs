@@ -0,0 +1,97 @@
+//! Polars expressions applying the scalar feature definitions of this crate to whole columns.
+
+use itertools::izip;
+use polars::prelude::*;
+
+use crate::{TimeFeatures, haversine_distance};
+
+///
+/// Distance between the pickup and dropoff coordinates, see [haversine_distance].
+/// Null if any coordinate is missing.
+///
+pub fn distance(plat: Expr, plong: Expr, dlat: Expr, dlong: Expr) -> Expr {
+    as_struct(vec![
+        plat.alias("plat"),
+        plong.alias("plong"),
+        dlat.alias("dlat"),
+        dlong.alias("dlong"),
+    ])
+    .map(
+        |s| {
+            let ca = s.struct_()?;
+            let plat = ca.field_by_name("plat")?;
+            let plong = ca.field_by_name("plong")?;
+            let dlat = ca.field_by_name("dlat")?;
+            let dlong = ca.field_by_name("dlong")?;
+
+            let out: Float64Chunked = izip!(
+                plat.f64()?.into_iter(),
+                plong.f64()?.into_iter(),
+                dlat.f64()?.into_iter(),
+                dlong.f64()?.into_iter()
+            )
+            .map(|(opt_plat, opt_plong, opt_dlat, opt_dlong)| {
+                match (opt_plat, opt_plong, opt_dlat, opt_dlong) {
+                    (Some(plat), Some(plong), Some(dlat), Some(dlong)) => {
+                        Some(haversine_distance(plat, plong, dlat, dlong))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+
+            Ok(Some(out.into_column()))
+        },
+        GetOutput::from_type(DataType::Float64),
+    )
+    .alias("distance")
+}
+
+///
+/// Features of [TimeFeatures] for a datetime column, named like its fields.
+/// The column can have any timezone, naive datetimes are taken as UTC.
+///
+pub fn time_features(pickup_datetime: Expr) -> [Expr; 4] {
+    let millis = pickup_datetime.dt().timestamp(TimeUnit::Milliseconds);
+    [
+        time_feature::<Int8Type>(millis.clone(), DataType::Int8, |features| {
+            features.pickup_hour as i8
+        })
+        .alias("pickup_hour"),
+        time_feature::<Int8Type>(millis.clone(), DataType::Int8, |features| {
+            features.pickup_weekday as i8
+        })
+        .alias("pickup_weekday"),
+        time_feature::<Int8Type>(millis.clone(), DataType::Int8, |features| {
+            features.am_or_pm as i8
+        })
+        .alias("am_or_pm"),
+        time_feature::<Int64Type>(millis, DataType::Int64, |features| {
+            features.pickup_timestamp
+        })
+        .alias("pickup_timestamp"),
+    ]
+}
+
+fn time_feature<T: PolarsNumericType>(
+    millis: Expr,
+    dtype: DataType,
+    feature: fn(&TimeFeatures) -> T::Native,
+) -> Expr {
+    millis.map(
+        move |s| {
+            let out: ChunkedArray<T> = s
+                .as_materialized_series()
+                .i64()?
+                .iter()
+                .map(|millis| {
+                    millis
+                        .and_then(TimeFeatures::from_timestamp_millis)
+                        .map(|features| feature(&features))
+                })
+                .collect();
+            Ok(Some(out.into_column()))
+        },
+        GetOutput::from_type(dtype),
+    )
+}
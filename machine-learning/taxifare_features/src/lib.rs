@@ -0,0 +1,87 @@
+use chrono::{DateTime, Datelike, TimeZone, Timelike};
+use chrono_tz::{America::New_York, Tz};
+
+pub mod expressions;
+
+const EARTH_RADIUS: f64 = 6371.0;
+/// Timezone in which the time based features are computed.
+pub const LOCAL_TIMEZONE: Tz = New_York;
+/// First local hour which counts as pm.
+const PM_START_HOUR: u8 = 12;
+
+///
+///Calculates the haversine distance between 2 sets of GPS coordinates in kilometers
+///
+pub fn haversine_distance(plat: f64, plong: f64, dlat: f64, dlong: f64) -> f64 {
+    let phi1 = plat.to_radians();
+    let phi2 = dlat.to_radians();
+
+    let delta_phi = (dlat - plat).to_radians();
+    let delta_lambda = (dlong - plong).to_radians();
+
+    let a = (delta_phi / 2.0).sin().powi(2)
+        + phi1.cos() * phi2.cos() * (delta_lambda / 2.0).sin().powi(2);
+    let c = 2.0 * libm::atan2(a.sqrt(), (1.0 - a).sqrt());
+    EARTH_RADIUS * c
+}
+
+/// 0 for am and 1 for pm.
+pub fn am_or_pm(hour: u8) -> u8 {
+    if hour < PM_START_HOUR { 0 } else { 1 }
+}
+
+/// Time based features of a pickup, local to [LOCAL_TIMEZONE].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimeFeatures {
+    pub pickup_hour: u8,
+    /// 0 is Monday.
+    pub pickup_weekday: u8,
+    pub am_or_pm: u8,
+    /// Seconds since the unix epoch.
+    pub pickup_timestamp: i64,
+}
+
+impl TimeFeatures {
+    pub fn new<Z: TimeZone>(pickup_datetime: &DateTime<Z>) -> Self {
+        let local = pickup_datetime.with_timezone(&LOCAL_TIMEZONE);
+        let pickup_hour = local.hour() as u8;
+        Self {
+            pickup_hour,
+            pickup_weekday: local.weekday().num_days_from_monday() as u8,
+            am_or_pm: am_or_pm(pickup_hour),
+            pickup_timestamp: local.timestamp(),
+        }
+    }
+
+    /// Returns `None` if the timestamp is out of the supported range.
+    pub fn from_timestamp_millis(millis: i64) -> Option<Self> {
+        DateTime::from_timestamp_millis(millis).map(|pickup_datetime| Self::new(&pickup_datetime))
+    }
+}
+
+/// All engineered features of a single trip.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TripFeatures {
+    pub distance: f64,
+    pub time: TimeFeatures,
+}
+
+impl TripFeatures {
+    pub fn new<Z: TimeZone>(
+        pickup_latitude: f64,
+        pickup_longitude: f64,
+        dropoff_latitude: f64,
+        dropoff_longitude: f64,
+        pickup_datetime: &DateTime<Z>,
+    ) -> Self {
+        Self {
+            distance: haversine_distance(
+                pickup_latitude,
+                pickup_longitude,
+                dropoff_latitude,
+                dropoff_longitude,
+            ),
+            time: TimeFeatures::new(pickup_datetime),
+        }
+    }
+}
@@ -0,0 +1,209 @@
+use chrono::{DateTime, Utc};
+use polars::prelude::*;
+use taxifare_features::{TimeFeatures, TripFeatures, expressions};
+
+/// Pickups around midnight, noon and both daylight saving transitions in New York.
+const PICKUPS: [&str; 10] = [
+    "2010-04-19 08:17:56 +0000",
+    "2010-04-17 15:43:53 +0000",
+    "2010-04-11 03:59:59 +0000",
+    "2010-04-11 04:00:00 +0000",
+    "2010-03-14 06:59:59 +0000",
+    "2010-03-14 07:00:00 +0000",
+    "2010-11-07 05:30:00 +0000",
+    "2010-11-07 06:30:00 +0000",
+    "2010-04-16 16:00:00 +0000",
+    "2010-04-16 15:59:59 +0000",
+];
+
+const COORDINATES: [(f64, f64, f64, f64); 10] = [
+    (40.730521, -73.992365, 40.744746, -73.975499),
+    (40.740558, -73.990078, 40.744114, -73.974892),
+    (40.751118, -73.994149, 40.766235, -73.988574),
+    (40.756422, -73.990485, 40.748268, -73.985999),
+    (40.734695, -73.980013, 40.747477, -73.973503),
+    (40.740558, -73.990078, 40.740558, -73.990078),
+    (40.645071, -73.784423, 40.758953, -73.969894),
+    (40.769088, -73.862835, 40.722193, -73.990623),
+    (40.761900, -73.978840, 40.705398, -74.008700),
+    (0.0, 0.0, 40.705398, -74.008700),
+];
+
+fn pickups() -> Vec<DateTime<Utc>> {
+    PICKUPS
+        .iter()
+        .map(|pickup| {
+            DateTime::parse_from_str(pickup, "%Y-%m-%d %H:%M:%S %z")
+                .expect("Sample pickup should be valid")
+                .to_utc()
+        })
+        .collect()
+}
+
+fn features_frame(df: DataFrame) -> DataFrame {
+    let [hour, weekday, am_or_pm, timestamp] = expressions::time_features(col("pickup_datetime"));
+    df.lazy()
+        .select([
+            expressions::distance(
+                col("pickup_latitude"),
+                col("pickup_longitude"),
+                col("dropoff_latitude"),
+                col("dropoff_longitude"),
+            ),
+            hour,
+            weekday,
+            am_or_pm,
+            timestamp,
+        ])
+        .collect()
+        .expect("Features should be computed")
+}
+
+fn sample_frame(time_zone: &str) -> DataFrame {
+    let millis: Vec<i64> = pickups()
+        .iter()
+        .map(|pickup| pickup.timestamp_millis())
+        .collect();
+    df!(
+        "pickup_latitude" => COORDINATES.map(|c| c.0),
+        "pickup_longitude" => COORDINATES.map(|c| c.1),
+        "dropoff_latitude" => COORDINATES.map(|c| c.2),
+        "dropoff_longitude" => COORDINATES.map(|c| c.3),
+        "pickup_datetime" => millis,
+    )
+    .expect("Sample frame should be valid")
+    .lazy()
+    .with_column(
+        col("pickup_datetime")
+            .cast(DataType::Datetime(
+                TimeUnit::Milliseconds,
+                Some("UTC".into()),
+            ))
+            .dt()
+            .convert_time_zone(time_zone.into()),
+    )
+    .collect()
+    .expect("Sample frame should be valid")
+}
+
+fn column<T>(
+    df: &DataFrame,
+    name: &str,
+    values: impl Fn(&Series) -> Vec<Option<T>>,
+) -> Vec<Option<T>> {
+    values(
+        df.column(name)
+            .expect("Feature column should exist")
+            .as_materialized_series(),
+    )
+}
+
+fn assert_matches_scalar(df: &DataFrame) {
+    let distance = column(df, "distance", |s| s.f64().unwrap().into_iter().collect());
+    let hour = column(df, "pickup_hour", |s| s.i8().unwrap().into_iter().collect());
+    let weekday = column(df, "pickup_weekday", |s| {
+        s.i8().unwrap().into_iter().collect()
+    });
+    let am_or_pm = column(df, "am_or_pm", |s| s.i8().unwrap().into_iter().collect());
+    let timestamp = column(df, "pickup_timestamp", |s| {
+        s.i64().unwrap().into_iter().collect()
+    });
+
+    for (index, (pickup, (plat, plong, dlat, dlong))) in
+        pickups().iter().zip(COORDINATES).enumerate()
+    {
+        let expected = TripFeatures::new(plat, plong, dlat, dlong, pickup);
+        assert_eq!(
+            distance[index],
+            Some(expected.distance),
+            "distance of trip {index}"
+        );
+        assert_eq!(
+            hour[index],
+            Some(expected.time.pickup_hour as i8),
+            "hour of trip {index}"
+        );
+        assert_eq!(
+            weekday[index],
+            Some(expected.time.pickup_weekday as i8),
+            "weekday of trip {index}"
+        );
+        assert_eq!(
+            am_or_pm[index],
+            Some(expected.time.am_or_pm as i8),
+            "am_or_pm of trip {index}"
+        );
+        assert_eq!(
+            timestamp[index],
+            Some(expected.time.pickup_timestamp),
+            "timestamp of trip {index}"
+        );
+    }
+}
+
+#[test]
+fn expressions_match_scalar_features() {
+    assert_matches_scalar(&features_frame(sample_frame("UTC")));
+}
+
+#[test]
+fn expressions_ignore_column_time_zone() {
+    assert_matches_scalar(&features_frame(sample_frame("America/New_York")));
+    assert_matches_scalar(&features_frame(sample_frame("Europe/Berlin")));
+}
+
+#[test]
+fn scalar_features_are_local_to_new_york() {
+    let pickups = pickups();
+    // 2010-04-19 04:17:56 EDT, a Monday.
+    assert_eq!(
+        TimeFeatures::new(&pickups[0]),
+        TimeFeatures {
+            pickup_hour: 4,
+            pickup_weekday: 0,
+            am_or_pm: 0,
+            pickup_timestamp: 1271665076,
+        }
+    );
+    // 2010-04-10 23:59:59 EDT is a Saturday, one second later it is Sunday.
+    assert_eq!(TimeFeatures::new(&pickups[2]).pickup_weekday, 5);
+    assert_eq!(TimeFeatures::new(&pickups[3]).pickup_weekday, 6);
+    // Clocks jump from 01:59:59 EST to 03:00:00 EDT.
+    assert_eq!(TimeFeatures::new(&pickups[4]).pickup_hour, 1);
+    assert_eq!(TimeFeatures::new(&pickups[5]).pickup_hour, 3);
+    // 01:30 happens twice when the clocks fall back.
+    assert_eq!(TimeFeatures::new(&pickups[6]).pickup_hour, 1);
+    assert_eq!(TimeFeatures::new(&pickups[7]).pickup_hour, 1);
+    // 12:00:00 EDT is the first pm second.
+    assert_eq!(TimeFeatures::new(&pickups[8]).am_or_pm, 1);
+    assert_eq!(TimeFeatures::new(&pickups[9]).am_or_pm, 0);
+}
+
+#[test]
+fn missing_values_give_null_features() {
+    let df = df!(
+        "pickup_latitude" => [Some(40.730521), None],
+        "pickup_longitude" => [-73.992365, -73.990078],
+        "dropoff_latitude" => [40.744746, 40.744114],
+        "dropoff_longitude" => [-73.975499, -73.974892],
+        "pickup_datetime" => [None, Some(1271665076000i64)],
+    )
+    .expect("Sample frame should be valid")
+    .lazy()
+    .with_column(col("pickup_datetime").cast(DataType::Datetime(
+        TimeUnit::Milliseconds,
+        Some("UTC".into()),
+    )))
+    .collect()
+    .expect("Sample frame should be valid");
+    let df = features_frame(df);
+
+    let distance = column(&df, "distance", |s| s.f64().unwrap().into_iter().collect());
+    let hour = column(&df, "pickup_hour", |s| {
+        s.i8().unwrap().into_iter().collect()
+    });
+    assert!(distance[0].is_some());
+    assert_eq!(distance[1], None);
+    assert_eq!(hour[0], None);
+    assert_eq!(hour[1], Some(4));
+}
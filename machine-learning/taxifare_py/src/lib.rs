@@ -0,0 +1,209 @@
+//! Python bindings of the taxi fare model, built with `maturin develop` in this directory.
+
+use std::{
+    io::ErrorKind,
+    sync::{Mutex, PoisonError},
+};
+
+use burn::backend::{Autodiff, NdArray, ndarray::NdArrayDevice};
+use chrono::DateTime;
+use linear_regression::{
+    models::taxifare_model::ModelConfig,
+    predictor::{TaxifarePredictor, Trip},
+    training::{ArtifactDirMode, TrainingConfig},
+};
+use numpy::{PyArray1, PyReadonlyArray1};
+use pyo3::{
+    exceptions::{PyIOError, PyValueError},
+    prelude::*,
+};
+use pyo3_polars::PyDataFrame;
+use serde_json::Value;
+
+type Backend = NdArray;
+
+/// Invalid inputs raise a `ValueError`, all other failures an `OSError`.
+fn py_error(err: std::io::Error) -> PyErr {
+    match err.kind() {
+        ErrorKind::InvalidInput | ErrorKind::InvalidData => PyValueError::new_err(err.to_string()),
+        _ => PyIOError::new_err(err.to_string()),
+    }
+}
+
+/// Reads a raw NYC taxi fares csv file and adds the engineered features.
+#[pyfunction]
+fn create_input_dataset(py: Python<'_>, filename: &str) -> PyResult<PyDataFrame> {
+    py.allow_threads(|| data_preparation::data::create_input_dataset(filename))
+        .map(PyDataFrame)
+        .map_err(|err| PyIOError::new_err(err.to_string()))
+}
+
+///
+/// Parses a training config, only `model` is required and all other keys default
+/// like in the `TrainingConfig` of the training CLI.
+///
+fn training_config(config_json: &str) -> PyResult<TrainingConfig> {
+    let invalid = |message: String| PyValueError::new_err(message);
+    let Value::Object(mut fields) =
+        serde_json::from_str(config_json).map_err(|err| invalid(err.to_string()))?
+    else {
+        return Err(invalid("The config has to be a JSON object".to_string()));
+    };
+    let model = fields
+        .remove("model")
+        .ok_or_else(|| invalid("The config has no model".to_string()))?;
+    let model: ModelConfig =
+        serde_json::from_value(model).map_err(|err| invalid(format!("Invalid model: {err}")))?;
+    let overrides = fields
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect::<Vec<_>>();
+    TrainingConfig::new(model)
+        .with_overrides(&overrides)
+        .map_err(|err| invalid(err.to_string()))
+}
+
+///
+/// Trains on the NdArray backend and saves the model to the artifact directory.
+/// Categorical values outside of the embeddings are listed in its categorical_violations.csv.
+///
+#[pyfunction]
+#[pyo3(signature = (config_json, data_path, artifact_dir, resume = false, force = false))]
+fn train(
+    py: Python<'_>,
+    config_json: &str,
+    data_path: &str,
+    artifact_dir: &str,
+    resume: bool,
+    force: bool,
+) -> PyResult<()> {
+    let config = training_config(config_json)?;
+    let mode = match (resume, force) {
+        (true, true) => {
+            return Err(PyValueError::new_err(
+                "resume and force can not be combined",
+            ));
+        }
+        (true, false) => ArtifactDirMode::Resume,
+        (false, true) => ArtifactDirMode::Force,
+        (false, false) => ArtifactDirMode::Fresh,
+    };
+    py.allow_threads(|| {
+        linear_regression::training::train::<Autodiff<Backend>>(
+            artifact_dir,
+            data_path,
+            config,
+            mode,
+            NdArrayDevice::Cpu,
+        )
+    })
+    .map(|_| ())
+    .map_err(py_error)
+}
+
+/// Predicts fares of raw trips with the model of an artifact directory.
+#[pyclass]
+struct Predictor {
+    // Burn modules are Send but not necessarily Sync.
+    predictor: Mutex<TaxifarePredictor<Backend>>,
+}
+
+#[pymethods]
+impl Predictor {
+    #[new]
+    fn new(py: Python<'_>, artifact_dir: &str) -> PyResult<Self> {
+        let predictor = py
+            .allow_threads(|| TaxifarePredictor::load(artifact_dir, NdArrayDevice::Cpu))
+            .map_err(py_error)?;
+        Ok(Self {
+            predictor: Mutex::new(predictor),
+        })
+    }
+
+    /// Training config of the model as JSON.
+    #[getter]
+    fn config(&self) -> PyResult<String> {
+        let predictor = self
+            .predictor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        serde_json::to_string(predictor.config())
+            .map_err(|err| PyValueError::new_err(err.to_string()))
+    }
+
+    ///
+    /// Fares of the trips given as arrays of equal length.
+    /// The pickup timestamps are seconds since the unix epoch in UTC,
+    /// e.g. `datetimes.astype("datetime64[s]").astype("int64")`.
+    ///
+    #[allow(clippy::too_many_arguments)]
+    fn predict<'py>(
+        &self,
+        py: Python<'py>,
+        pickup_latitude: PyReadonlyArray1<'py, f64>,
+        pickup_longitude: PyReadonlyArray1<'py, f64>,
+        dropoff_latitude: PyReadonlyArray1<'py, f64>,
+        dropoff_longitude: PyReadonlyArray1<'py, f64>,
+        pickup_timestamp: PyReadonlyArray1<'py, i64>,
+        passenger_count: PyReadonlyArray1<'py, f64>,
+    ) -> PyResult<Bound<'py, PyArray1<f32>>> {
+        let pickup_latitude = pickup_latitude.as_array();
+        let pickup_longitude = pickup_longitude.as_array();
+        let dropoff_latitude = dropoff_latitude.as_array();
+        let dropoff_longitude = dropoff_longitude.as_array();
+        let pickup_timestamp = pickup_timestamp.as_array();
+        let passenger_count = passenger_count.as_array();
+
+        let len = pickup_latitude.len();
+        if [
+            pickup_longitude.len(),
+            dropoff_latitude.len(),
+            dropoff_longitude.len(),
+            pickup_timestamp.len(),
+            passenger_count.len(),
+        ]
+        .iter()
+        .any(|other| *other != len)
+        {
+            return Err(PyValueError::new_err(
+                "All arrays must have the same length",
+            ));
+        }
+
+        let trips = (0..len)
+            .map(|i| {
+                let pickup_datetime =
+                    DateTime::from_timestamp(pickup_timestamp[i], 0).ok_or_else(|| {
+                        PyValueError::new_err(format!(
+                            "Pickup timestamp {} is out of range",
+                            pickup_timestamp[i]
+                        ))
+                    })?;
+                Ok(Trip {
+                    pickup_latitude: pickup_latitude[i],
+                    pickup_longitude: pickup_longitude[i],
+                    dropoff_latitude: dropoff_latitude[i],
+                    dropoff_longitude: dropoff_longitude[i],
+                    pickup_datetime: pickup_datetime.fixed_offset(),
+                    passenger_count: passenger_count[i],
+                })
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+
+        let fares = py.allow_threads(|| {
+            self.predictor
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .predict_batch(&trips)
+        });
+        Ok(PyArray1::from_vec(py, fares))
+    }
+}
+
+#[pymodule]
+fn taxifare(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(create_input_dataset, module)?)?;
+    module.add_function(wrap_pyfunction!(train, module)?)?;
+    module.add_class::<Predictor>()?;
+    Ok(())
+}
@@ -0,0 +1,160 @@
+"""Tests of the Python bindings, run with `maturin develop && pytest` in taxifare_py."""
+
+import json
+import random
+
+import numpy as np
+import polars as pl
+import pytest
+
+import taxifare
+
+MODEL = {
+    "embedding_sizes": [[7, 4], [24, 12], [2, 1]],
+    "n_cont": 6,
+    "layers": [16, 8],
+    "dropout": 0.1,
+}
+
+PREPARED_COLUMNS = [
+    "fare_amount",
+    "pickup_latitude",
+    "pickup_longitude",
+    "dropoff_latitude",
+    "dropoff_longitude",
+    "passenger_count",
+    "distance",
+    "pickup_hour",
+    "pickup_weekday",
+    "am_or_pm",
+    "pickup_timestamp",
+]
+
+
+@pytest.fixture(scope="module")
+def raw_file(tmp_path_factory):
+    rng = random.Random(7)
+    rows = []
+    for i in range(200):
+        rows.append(
+            {
+                "pickup_datetime": f"2010-04-{1 + i % 28:02d} {i % 24:02d}:17:56 UTC",
+                "fare_amount": round(rng.uniform(3.0, 40.0), 2),
+                "fare_class": 0,
+                "pickup_longitude": -73.99 + rng.uniform(-0.05, 0.05),
+                "pickup_latitude": 40.73 + rng.uniform(-0.05, 0.05),
+                "dropoff_longitude": -73.97 + rng.uniform(-0.05, 0.05),
+                "dropoff_latitude": 40.75 + rng.uniform(-0.05, 0.05),
+                "passenger_count": 1 + i % 4,
+            }
+        )
+    path = tmp_path_factory.mktemp("data") / "trips.csv"
+    pl.DataFrame(rows).write_csv(path)
+    return str(path)
+
+
+@pytest.fixture(scope="module")
+def artifact_dir(raw_file, tmp_path_factory):
+    prepared = tmp_path_factory.mktemp("data") / "prepared.csv"
+    taxifare.create_input_dataset(raw_file).select(
+        pl.col(PREPARED_COLUMNS[:5]),
+        pl.col("passenger_count").cast(pl.Float64),
+        pl.col(PREPARED_COLUMNS[6:]),
+    ).write_csv(prepared)
+
+    artifact_dir = str(tmp_path_factory.mktemp("artifacts"))
+    config = {"model": MODEL, "num_epochs": 1, "batch_size": 32, "feature_cache": False}
+    taxifare.train(json.dumps(config), str(prepared), artifact_dir, force=True)
+    return artifact_dir
+
+
+def test_create_input_dataset_adds_features(raw_file):
+    df = taxifare.create_input_dataset(raw_file)
+    assert isinstance(df, pl.DataFrame)
+    assert df.height == 200
+    for column in ["distance", "pickup_hour", "pickup_weekday", "am_or_pm", "pickup_timestamp"]:
+        assert column in df.columns
+    # 2010-04-01 00:17:56 UTC is Wednesday 20:17:56 in New York.
+    first = df.row(0, named=True)
+    assert first["pickup_hour"] == 20
+    assert first["pickup_weekday"] == 2
+    assert first["am_or_pm"] == 1
+
+
+def test_train_rejects_config_without_model(tmp_path):
+    with pytest.raises(ValueError):
+        taxifare.train(json.dumps({"num_epochs": 1}), "unused.csv", str(tmp_path))
+
+
+def test_train_reports_missing_data_file(tmp_path):
+    config = {"model": MODEL, "num_epochs": 1}
+    with pytest.raises(OSError):
+        taxifare.train(
+            json.dumps(config), str(tmp_path / "missing.csv"), str(tmp_path / "artifacts")
+        )
+
+
+def test_train_refuses_to_overwrite_artifacts(artifact_dir, raw_file):
+    config = {"model": MODEL, "num_epochs": 1}
+    with pytest.raises(OSError):
+        taxifare.train(json.dumps(config), raw_file, artifact_dir)
+
+
+def test_predictor_reports_missing_artifact_dir(tmp_path):
+    with pytest.raises(OSError, match="does not exist"):
+        taxifare.Predictor(str(tmp_path / "missing"))
+
+
+def test_predictor_reports_incomplete_artifact_dir(tmp_path):
+    (tmp_path / "config.json").write_text("{}")
+    with pytest.raises(OSError):
+        taxifare.Predictor(str(tmp_path))
+
+
+def test_predictor_predicts_numpy_arrays(raw_file, artifact_dir):
+    predictor = taxifare.Predictor(artifact_dir)
+    assert json.loads(predictor.config)["model"]["layers"] == [16, 8]
+
+    df = pl.read_csv(raw_file)
+    timestamps = (
+        df["pickup_datetime"]
+        .str.to_datetime("%Y-%m-%d %H:%M:%S UTC", time_zone="UTC")
+        .dt.epoch("s")
+        .to_numpy()
+    )
+    fares = predictor.predict(
+        df["pickup_latitude"].to_numpy(),
+        df["pickup_longitude"].to_numpy(),
+        df["dropoff_latitude"].to_numpy(),
+        df["dropoff_longitude"].to_numpy(),
+        timestamps.astype(np.int64),
+        df["passenger_count"].cast(pl.Float64).to_numpy(),
+    )
+    assert isinstance(fares, np.ndarray)
+    assert fares.dtype == np.float32
+    assert fares.shape == (200,)
+    assert np.isfinite(fares).all()
+
+    single = predictor.predict(
+        df["pickup_latitude"].to_numpy()[:1],
+        df["pickup_longitude"].to_numpy()[:1],
+        df["dropoff_latitude"].to_numpy()[:1],
+        df["dropoff_longitude"].to_numpy()[:1],
+        timestamps.astype(np.int64)[:1],
+        df["passenger_count"].cast(pl.Float64).to_numpy()[:1],
+    )
+    assert single[0] == pytest.approx(fares[0], abs=1e-5)
+
+
+def test_predictor_rejects_arrays_of_different_length(artifact_dir):
+    predictor = taxifare.Predictor(artifact_dir)
+    coordinates = np.array([40.73, 40.74])
+    with pytest.raises(ValueError):
+        predictor.predict(
+            coordinates,
+            coordinates,
+            coordinates,
+            coordinates,
+            np.array([1271665076], dtype=np.int64),
+            np.array([1.0, 2.0]),
+        )
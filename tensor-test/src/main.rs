@@ -1,11 +1,28 @@
-use burn::backend::Wgpu;
-use burn::tensor::Tensor;
+use burn::tensor::{Tensor, backend::Backend};
 
-fn main() {
-    type MyBackend = Wgpu<f32, i32>;
-    let device = burn::backend::wgpu::WgpuDevice::DefaultDevice;
-    let tensor_3d: Tensor<MyBackend, 2> =
+fn print_tensor<B: Backend>(device: B::Device) {
+    let tensor_3d: Tensor<B, 2> =
         Tensor::from_data([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]], &device);
     println!("{tensor_3d:?}");
     println!("{:?}", tensor_3d.shape());
 }
+
+/// The backend is selected with `TAXIFARE_BACKEND` like in `linear_regression`,
+/// NdArray is used if it is not set.
+#[allow(unreachable_patterns)]
+fn main() {
+    let backend = std::env::var("TAXIFARE_BACKEND").unwrap_or_else(|_| "ndarray".to_string());
+    match backend.to_lowercase().as_str() {
+        #[cfg(feature = "ndarray")]
+        "ndarray" => print_tensor::<burn::backend::NdArray<f32>>(Default::default()),
+        #[cfg(feature = "candle")]
+        "candle" => print_tensor::<burn::backend::Candle<f32, i64>>(Default::default()),
+        #[cfg(feature = "tch")]
+        "tch" | "libtorch" => print_tensor::<burn::backend::LibTorch<f32>>(Default::default()),
+        #[cfg(feature = "wgpu")]
+        "wgpu" => print_tensor::<burn::backend::Wgpu<f32, i32>>(Default::default()),
+        #[cfg(feature = "metal")]
+        "metal" => print_tensor::<burn::backend::Metal<f32, i32>>(Default::default()),
+        _ => panic!("Backend {backend} is not compiled in, enable the feature of the same name."),
+    }
+}